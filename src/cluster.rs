@@ -0,0 +1,78 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+//
+// Fuzzy stack-trace clustering, modeled on CASR's cluster step. Exact
+// stack-hash dedup (see `stackhash`) over-splits crashes that differ by one
+// inlined frame or a slightly different offset, so cluster near-duplicate
+// traces together instead, using single-linkage agglomeration over a
+// normalized-LCS distance.
+
+pub const DEFAULT_CLUSTER_THRESHOLD: f32 = 0.3;
+
+/// Longest common subsequence length between two sequences of normalized
+/// frame identifiers.
+fn lcs_len(a: &[String], b: &[String]) -> usize {
+    let mut dp = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            dp[i][j] = if a[i - 1] == b[j - 1] {
+                dp[i - 1][j - 1] + 1
+            } else {
+                dp[i - 1][j].max(dp[i][j - 1])
+            };
+        }
+    }
+
+    dp[a.len()][b.len()]
+}
+
+/// `1 - 2*LCS(a,b) / (len(a)+len(b))`: 0.0 for identical traces, approaching
+/// 1.0 for traces sharing no common frames.
+pub fn distance(a: &[String], b: &[String]) -> f32 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+
+    let lcs = lcs_len(a, b) as f32;
+    1.0 - (2.0 * lcs) / ((a.len() + b.len()) as f32)
+}
+
+/// Single-linkage agglomerative clustering: `traces[i]` joins the first
+/// existing cluster containing a member within `threshold` distance of it,
+/// otherwise it starts a new cluster. Processed in the given order, so
+/// callers that want deterministic cluster assignment (triage itself runs in
+/// parallel) should sort/stabilize `traces` first.
+///
+/// Returns, for each input trace, the index of the cluster it was assigned
+/// to. Cluster indices are in `[0, cluster_count)` and are assigned in the
+/// order clusters were created.
+pub fn cluster(traces: &[Vec<String>], threshold: f32) -> Vec<usize> {
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut assignment = vec![0usize; traces.len()];
+
+    for (i, trace) in traces.iter().enumerate() {
+        let mut joined = None;
+
+        'clusters: for (cluster_id, members) in clusters.iter().enumerate() {
+            for &member in members {
+                if distance(trace, &traces[member]) < threshold {
+                    joined = Some(cluster_id);
+                    break 'clusters;
+                }
+            }
+        }
+
+        let cluster_id = joined.unwrap_or(clusters.len());
+
+        if joined.is_none() {
+            clusters.push(Vec::new());
+        }
+
+        clusters[cluster_id].push(i);
+        assignment[i] = cluster_id;
+    }
+
+    assignment
+}
@@ -1,10 +1,14 @@
 // Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
 //
 // SPDX-License-Identifier: BSD-3-Clause
+use nix::poll::{poll, PollFd, PollFlags};
 use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::os::unix::io::AsRawFd;
 use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout};
+use std::time::{Duration, Instant};
 use tempfile;
-use std::io::{Error, ErrorKind, Write};
 
 use crate::process::{self, ChildResult};
 
@@ -111,16 +115,89 @@ pub struct GdbThread {
     pub registers: Option<Vec<GdbRegister>>,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Serialize)]
 pub struct GdbRegister {
     pub name: String,
-    pub value: u64,
+    /// Raw register value, little-endian, `size` bytes long. A plain `u64`
+    /// silently truncated xmm/ymm/zmm, NEON, and other vector registers
+    /// wider than 64 bits, which are frequently the faulting values in
+    /// SIMD-heavy crashes; GDB/LLDB's own target-description register sets
+    /// expose arbitrary-width registers for the same reason.
+    pub value_bytes: Vec<u8>,
     pub pretty_value: String,
     pub r#type: String,
     /// Size in bytes
     pub size: u64,
 }
 
+impl GdbRegister {
+    /// Reconstruct a `u64` from `value_bytes`, for registers that fit in one
+    /// (general-purpose and flag registers). `None` for wider registers
+    /// (xmm/ymm/zmm, NEON, ...).
+    pub fn as_u64(&self) -> Option<u64> {
+        if self.value_bytes.len() > 8 {
+            return None;
+        }
+
+        let mut buf = [0u8; 8];
+        buf[..self.value_bytes.len()].copy_from_slice(&self.value_bytes);
+        Some(u64::from_le_bytes(buf))
+    }
+
+    /// Reconstruct a `u128` from `value_bytes`, for registers up to 128 bits
+    /// wide (covers xmm and 128-bit NEON registers). `None` for wider ones
+    /// (ymm/zmm).
+    pub fn as_u128(&self) -> Option<u128> {
+        if self.value_bytes.len() > 16 {
+            return None;
+        }
+
+        let mut buf = [0u8; 16];
+        buf[..self.value_bytes.len()].copy_from_slice(&self.value_bytes);
+        Some(u128::from_le_bytes(buf))
+    }
+}
+
+// Old reports serialized a scalar `value: u64`, which truncated anything
+// wider; accept that shape too so old reports still deserialize, upconverting
+// it into a `value_bytes`.
+impl<'de> Deserialize<'de> for GdbRegister {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawGdbRegister {
+            name: String,
+            #[serde(default)]
+            value: Option<u64>,
+            #[serde(default)]
+            value_bytes: Option<Vec<u8>>,
+            pretty_value: String,
+            r#type: String,
+            size: u64,
+        }
+
+        let raw = RawGdbRegister::deserialize(deserializer)?;
+
+        let value_bytes = match raw.value_bytes {
+            Some(bytes) => bytes,
+            None => match raw.value {
+                Some(value) => value.to_le_bytes().to_vec(),
+                None => return Err(serde::de::Error::custom("GdbRegister has neither value_bytes nor value")),
+            },
+        };
+
+        Ok(GdbRegister {
+            name: raw.name,
+            value_bytes,
+            pretty_value: raw.pretty_value,
+            r#type: raw.r#type,
+            size: raw.size,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GdbStopInfo {
     pub signal: String,
@@ -129,11 +206,76 @@ pub struct GdbStopInfo {
     pub faulting_address: Option<u64>, // sigfault.si_addr
 }
 
+/// One mapped region of the target's address space, from GDB's `info proc
+/// mappings` (or equivalent remote section-offset data), used to recover
+/// each module's load bias so crash addresses from ASLR-enabled runs are
+/// reproducibly comparable across runs of the same binary.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemoryRegion {
+    pub start: u64,
+    pub end: u64,
+    pub permissions: String,
+    /// Backing module/file path, if any (anonymous mappings have none)
+    pub backing_file: Option<String>,
+    /// Offset into `backing_file` that `start` corresponds to
+    pub file_offset: u64,
+}
+
+impl MemoryRegion {
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.start && address < self.end
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GdbContextInfo {
     pub stop_info: GdbStopInfo,
     pub primary_thread: GdbThread,
     pub other_threads: Option<Vec<GdbThread>>,
+    /// The process's memory map at the time of the crash, populated from
+    /// `info proc mappings` in `gdb/triage.py`. Empty for old reports that
+    /// predate this field.
+    #[serde(default)]
+    pub memory_regions: Vec<MemoryRegion>,
+    /// The trailing syscall trace recorded by an opt-in `catch syscall`
+    /// instrumentation pass (see `SyscallTraceConfig`), bounded to its
+    /// configured ring buffer size. `None` when tracing wasn't requested,
+    /// distinct from `Some(vec![])` meaning it was requested but the target
+    /// made no matching syscalls before it crashed.
+    #[serde(default)]
+    pub syscall_trace: Option<Vec<SyscallEvent>>,
+}
+
+impl GdbContextInfo {
+    /// The `(backing file, file-relative offset)` of `address`, with its
+    /// module's ASLR load bias removed, so two runs of the same binary
+    /// under different load bases produce identical relative addresses.
+    /// `None` if `address` isn't covered by any known mapping (e.g. JIT'd
+    /// or otherwise unbacked memory) or the mapping has no backing file.
+    pub fn file_relative_offset(&self, address: u64) -> Option<(String, u64)> {
+        let region = self.memory_regions.iter().find(|region| region.contains(address))?;
+        let backing_file = region.backing_file.as_ref()?;
+        let load_bias = region.start - region.file_offset;
+
+        Some((backing_file.clone(), address - load_bias))
+    }
+}
+
+/// One hit of a `catch syscall` catchpoint installed by an opt-in
+/// `SyscallTraceConfig` (see `GdbTriager::with_syscall_trace`), recording
+/// just enough to answer "what was this process doing right before it
+/// crashed" without a separate strace/ptrace run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyscallEvent {
+    pub name: String,
+    /// The raw argument registers GDB reported for the syscall (e.g. rdi,
+    /// rsi, rdx, ... on x86_64), in calling-convention order
+    pub args: Vec<u64>,
+    /// The return value from the syscall's exit. GDB's `catch syscall`
+    /// fires on both entry and exit; `gdb/triage.py` only records an event
+    /// once its exit hit is seen, so it always fills this in, and a syscall
+    /// whose exit was never hit is left out of the trace
+    pub retval: Option<i64>,
 }
 
 // can be blank ({}) meaning error or target exited
@@ -150,9 +292,9 @@ pub struct GdbTriageResult {
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum GdbTriageErrorKind {
-    ErrorCommand,
-    ErrorInternal,
-    ErrorTimeout,
+    Command,
+    Internal,
+    Timeout,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -252,9 +394,85 @@ enum GdbTriageScript {
     Internal(tempfile::NamedTempFile)
 }
 
+/// A `gdbserver` (or other GDB Remote Serial Protocol stub) to triage
+/// against instead of spawning the target locally, for devices/sandboxes
+/// AFLTriage isn't running on directly.
+#[derive(Debug, Clone)]
+pub struct GdbRemoteTarget {
+    pub host: String,
+    pub port: u16,
+    /// Use `target extended-remote` (lets GDB ask the stub to re-run the
+    /// target between testcases) instead of plain `target remote`.
+    pub extended: bool,
+}
+
+impl GdbRemoteTarget {
+    fn connect_command(&self) -> String {
+        let kind = if self.extended { "extended-remote" } else { "remote" };
+        format!("target {} {}:{}", kind, self.host, self.port)
+    }
+}
+
+/// Which syscalls an opt-in `SyscallTraceConfig` installs `catch syscall`
+/// catchpoints for.
+#[derive(Debug, Clone)]
+pub enum SyscallFilter {
+    /// `catch syscall` with no names, i.e. every syscall
+    All,
+    /// `catch syscall open read write ...`
+    Named(Vec<String>),
+}
+
+impl SyscallFilter {
+    fn gdb_catch_command(&self) -> String {
+        match self {
+            SyscallFilter::All => "catch syscall".to_string(),
+            SyscallFilter::Named(names) => format!("catch syscall {}", names.join(" ")),
+        }
+    }
+}
+
+/// Default size of the `syscall_trace` ring buffer: large enough to capture
+/// the lead-up to a crash without a long-running or looping target blowing
+/// the report up.
+pub const DEFAULT_SYSCALL_RING_BUFFER: usize = 32;
+
+/// Configuration for the optional syscall catchpoint trace (see
+/// `GdbTriager::with_syscall_trace`). Off by default: installing catchpoints
+/// and recording every hit adds per-syscall overhead that most triage runs
+/// don't want to pay.
+#[derive(Debug, Clone)]
+pub struct SyscallTraceConfig {
+    pub filter: SyscallFilter,
+    /// Only the last `ring_buffer_size` syscalls are kept in
+    /// `GdbContextInfo::syscall_trace`
+    pub ring_buffer_size: usize,
+}
+
+/// A debugger backend capable of producing `GdbTriageResult`s. `GdbTriager`
+/// (the GDB/Python backend, below) and `lldb_triage::LldbTriager` (the
+/// LLDB/Python backend) both implement this so the rest of the pipeline
+/// doesn't care which debugger actually ran the target; both emit the same
+/// `GdbContextInfo`/`GdbStopInfo`/`GdbThread` JSON schema, so downstream
+/// formatting and classification stay unchanged either way.
+pub trait Triager {
+    fn has_supported_debugger(&self) -> bool;
+    fn triage_program(
+        &self,
+        prog_args: &[String],
+        input_file: Option<&str>,
+        show_raw_output: bool,
+        timeout_ms: u64,
+        limits: process::ResourceLimits,
+    ) -> Result<GdbTriageResult, GdbTriageError>;
+}
+
 pub struct GdbTriager {
     triage_script: GdbTriageScript,
-    gdb: String
+    gdb: String,
+    remote_target: Option<GdbRemoteTarget>,
+    syscall_trace: Option<SyscallTraceConfig>,
+    rlimit_wrapper: tempfile::NamedTempFile,
 }
 
 impl GdbTriager {
@@ -271,8 +489,48 @@ impl GdbTriager {
             _ => ()
         }
 
+        let rlimit_wrapper = process::write_rlimit_wrapper();
+
         // TODO: allow user to select GDB
-        GdbTriager { triage_script, gdb: "gdb".to_string() }
+        GdbTriager { triage_script, gdb: "gdb".to_string(), remote_target: None, syscall_trace: None, rlimit_wrapper }
+    }
+
+    /// The `-ex "set environment ..."` commands that hand `limits` to
+    /// `RLIMIT_WRAPPER_SCRIPT` and point GDB's inferior-launching shell at
+    /// it (by pointing `$SHELL` at it, since `run`/`start` exec the
+    /// inferior via `$SHELL -c "exec ..."` when "startup-with-shell" is on,
+    /// which is the default), so the limits land on the debuggee instead
+    /// of on GDB itself. Must run before the first `run`/`continue`.
+    fn rlimit_env_gdb_args(&self, limits: process::ResourceLimits) -> Vec<String> {
+        let mut args = vec_of_strings!("-ex", format!("set environment SHELL {}", self.rlimit_wrapper.path().to_str().unwrap()));
+
+        for (name, value) in process::rlimit_env_vars(limits) {
+            args.extend(vec_of_strings!("-ex", format!("set environment {} {}", name, value)));
+        }
+
+        args
+    }
+
+    /// Triage against a `gdbserver` reachable at `target`'s host:port
+    /// instead of spawning the target locally.
+    pub fn with_remote_target(mut self, target: GdbRemoteTarget) -> GdbTriager {
+        self.remote_target = Some(target);
+        self
+    }
+
+    /// Opt into recording a bounded trailing trace of syscalls the target
+    /// made before it crashed (`GdbContextInfo::syscall_trace`), useful for
+    /// triaging bugs whose fault depends on I/O or environment.
+    pub fn with_syscall_trace(mut self, config: SyscallTraceConfig) -> GdbTriager {
+        self.syscall_trace = Some(config);
+        self
+    }
+
+    /// Whether this triager is configured for a remote target. `GdbSession`
+    /// only knows how to spawn a local `--args` child today, so callers
+    /// should stick to the `triage_program` batch path when this is true.
+    pub fn is_remote(&self) -> bool {
+        self.remote_target.is_some()
     }
 
     pub fn has_supported_gdb(&self) -> bool {
@@ -311,47 +569,41 @@ impl GdbTriager {
         true
     }
 
-    pub fn triage_program(&self, prog_args: Vec<String>, input_file: Option<&str>, show_raw_output: bool, timeout_ms: u64) -> Result<GdbTriageResult, GdbTriageError> {
+    /// Extracts the path to the triage script, relaunching GDB once per
+    /// testcase (see `GdbSession` for the persistent-session alternative)
+    /// and recovering its output by scanning for the `DbgMarker` text
+    /// markers injected into both stdout and stderr. This is the fallback
+    /// path used when a `GdbSession` can't be started, or respawned after
+    /// one breaks down.
+    pub fn triage_program(&self, prog_args: &[String], input_file: Option<&str>, show_raw_output: bool, timeout_ms: u64, limits: process::ResourceLimits) -> Result<GdbTriageResult, GdbTriageError> {
         let triage_script_path = match &self.triage_script  {
             GdbTriageScript::Internal(tf) => tf.path(),
-            _ => return Err(GdbTriageError::new_brief(GdbTriageErrorKind::ErrorInternal, "Unsupported triage script path")),
+            _ => return Err(GdbTriageError::new_brief(GdbTriageErrorKind::Internal, "Unsupported triage script path")),
         };
 
-        let gdb_run_command = match input_file {
-            // GDB overwrites args in the format (damn you)
-            Some(file) => format!("run {} < \"{}\"", &prog_args[1..].join(" "), file),
-            None => format!("run"),
+        let gdb_args = match &self.remote_target {
+            // A remote target isn't spawned by us at all (gdbserver already
+            // has it running), so there's no local debuggee to scope
+            // `limits` to.
+            Some(target) => Self::remote_gdb_args(target, prog_args, input_file, triage_script_path, self.syscall_trace.as_ref()),
+            None => Self::local_gdb_args(prog_args, input_file, triage_script_path, self.syscall_trace.as_ref(), &self.rlimit_env_gdb_args(limits)),
         };
 
-        // TODO: memory limit?
-        let gdb_args = vec_of_strings!(
-                            "--batch", "--nx",
-                            "-iex", "set index-cache on",
-                            "-iex", "set index-cache directory gdb_cache",
-                            // write the marker to both stdout and stderr as they are not interleaved
-                            "-ex", MARKER_CHILD_OUTPUT.gdb_start,
-                            "-ex", "set logging file /dev/null",
-                            "-ex", "set logging redirect on",
-                            "-ex", "set logging on",
-                            "-ex", gdb_run_command,
-                            "-ex", "set logging redirect off",
-                            "-ex", "set logging off",
-                            "-ex", MARKER_CHILD_OUTPUT.gdb_end,
-                            "-ex", MARKER_BACKTRACE.gdb_start,
-                            "-x", triage_script_path.to_str().unwrap(),
-                            "-ex", MARKER_BACKTRACE.gdb_end,
-                            "--args");
-
-        let gdb_cmdline = &[&gdb_args[..], &prog_args[..]].concat();
-        let gdb_cmd_fmt = [std::slice::from_ref(&self.gdb), gdb_cmdline].concat().join(" ");
+        let gdb_cmd_fmt = [std::slice::from_ref(&self.gdb), &gdb_args[..]].concat().join(" ");
 
-        let output = match process::execute_capture_output_timeout(&self.gdb, gdb_cmdline, timeout_ms) {
+        // Never write to stdin ourselves; GDB passes the testcase to the
+        // target via "run < FILE" (or, for a remote target, "remote put")
+        // above when needed. `limits` is applied to the debuggee via
+        // `RLIMIT_WRAPPER_SCRIPT`/`rlimit_env_gdb_args` above, not here:
+        // applying it to this `Command` would bound GDB's own process
+        // instead, since `setrlimit` persists across `exec`.
+        let output = match process::execute_capture_output_timeout(&self.gdb, &gdb_args, timeout_ms, None, None) {
             Ok(o) => o,
             Err(e) => {
                 return if e.kind() == ErrorKind::TimedOut {
-                    Err(GdbTriageError::new(GdbTriageErrorKind::ErrorTimeout, "Timed out when triaging", e.to_string()))
+                    Err(GdbTriageError::new(GdbTriageErrorKind::Timeout, "Timed out when triaging", e.to_string()))
                 } else {
-                    Err(GdbTriageError::new(GdbTriageErrorKind::ErrorCommand, "Failed to execute GDB command", e.to_string()))
+                    Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to execute GDB command", e.to_string()))
                 };
             }
         };
@@ -366,27 +618,27 @@ impl GdbTriager {
 
         let child_output_stdout = match MARKER_CHILD_OUTPUT.extract(decoded_stdout) {
             Ok(output) => output.to_string(),
-            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::ErrorCommand, "Could not extract child STDOUT", e.to_string())),
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Could not extract child STDOUT", e.to_string())),
         };
 
         let child_output_stderr = match MARKER_CHILD_OUTPUT.extract(decoded_stderr) {
             Ok(output) => output.to_string(),
-            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::ErrorCommand, "Could not extract child STDERR", e.to_string())),
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Could not extract child STDERR", e.to_string())),
         };
 
         let backtrace_output = match MARKER_BACKTRACE.extract(decoded_stdout) {
             Ok(output) => output,
-            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::ErrorCommand, "Failed to get triage JSON from GDB", e.to_string())),
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to get triage JSON from GDB", e.to_string())),
         };
 
         let backtrace_messages = match MARKER_BACKTRACE.extract(decoded_stderr) {
             Ok(output) => output,
-            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::ErrorCommand, "Failed to get triage errors from GDB", e.to_string())),
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to get triage errors from GDB", e.to_string())),
         };
 
         if backtrace_output.is_empty() {
             if !backtrace_messages.is_empty() {
-                return Err(GdbTriageError::new_detailed(GdbTriageErrorKind::ErrorCommand, "Triage script emitted errors", backtrace_messages.lines().map(str::to_string).collect()))
+                return Err(GdbTriageError::new_detailed(GdbTriageErrorKind::Command, "Triage script emitted errors", backtrace_messages.lines().map(str::to_string).collect()))
             }
         }
 
@@ -399,11 +651,369 @@ impl GdbTriager {
                     status: output.status,
                 },
             }),
-            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::ErrorCommand, "Failed to parse triage JSON from GDB", e.to_string())),
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to parse triage JSON from GDB", e.to_string())),
         };
     }
 
     fn parse_response(&self, resp: &str) -> serde_json::Result<GdbJsonResult> {
         serde_json::from_str(resp)
     }
+
+    /// The `-iex`/`-ex` arguments that install the syscall catchpoints and
+    /// triage script plumbing an opt-in `SyscallTraceConfig` needs, run
+    /// before the target is `run`/`continue`d. The script is sourced early
+    /// (it's idempotent to sourcing twice) so `aflTriageRecordSyscallHit` is
+    /// already defined by the time the first catchpoint fires; the ring
+    /// buffer size is handed to it via `os.environ` since GDB has no native
+    /// way to pass arguments into a sourced script, while the name filter
+    /// goes into the `catch syscall` command itself.
+    fn syscall_trace_gdb_args(config: &SyscallTraceConfig, triage_script_path: &std::path::Path) -> Vec<String> {
+        vec_of_strings!(
+            "-iex", format!(
+                "python import os; os.environ['AFLTRIAGE_SYSCALL_RING_SIZE'] = '{}'",
+                config.ring_buffer_size
+            ),
+            "-iex", format!("source {}", triage_script_path.to_str().unwrap()),
+            "-ex", config.filter.gdb_catch_command(),
+            "-ex", "commands\nsilent\npython aflTriageRecordSyscallHit()\ncontinue\nend"
+        )
+    }
+
+    /// The local-target GDB command line: spawn `prog_args` directly as a
+    /// child via `--args`, same as always.
+    fn local_gdb_args(prog_args: &[String], input_file: Option<&str>, triage_script_path: &std::path::Path, syscall_trace: Option<&SyscallTraceConfig>, rlimit_args: &[String]) -> Vec<String> {
+        let gdb_run_command = match input_file {
+            // GDB overwrites args in the format (damn you)
+            Some(file) => format!("run {} < \"{}\"", &prog_args[1..].join(" "), file),
+            None => format!("run"),
+        };
+
+        let mut gdb_args = vec_of_strings!(
+                            "--batch", "--nx",
+                            "-iex", "set index-cache on",
+                            "-iex", "set index-cache directory gdb_cache");
+
+        // Must run before "run" below: bounds the debuggee, not GDB itself
+        // (see `RLIMIT_WRAPPER_SCRIPT`).
+        gdb_args.extend_from_slice(rlimit_args);
+
+        if let Some(config) = syscall_trace {
+            gdb_args.extend(Self::syscall_trace_gdb_args(config, triage_script_path));
+        }
+
+        gdb_args.extend(vec_of_strings!(
+                            // write the marker to both stdout and stderr as they are not interleaved
+                            "-ex", MARKER_CHILD_OUTPUT.gdb_start,
+                            "-ex", "set logging file /dev/null",
+                            "-ex", "set logging redirect on",
+                            "-ex", "set logging on",
+                            "-ex", gdb_run_command,
+                            "-ex", "set logging redirect off",
+                            "-ex", "set logging off",
+                            "-ex", MARKER_CHILD_OUTPUT.gdb_end,
+                            "-ex", MARKER_BACKTRACE.gdb_start,
+                            "-x", triage_script_path.to_str().unwrap(),
+                            "-ex", MARKER_BACKTRACE.gdb_end,
+                            "--args");
+
+        [&gdb_args[..], prog_args].concat()
+    }
+
+    /// The remote-target GDB command line: no `--args` (the target is
+    /// already running under `gdbserver`, not spawned by us), so attach with
+    /// `target remote`/`target extended-remote` instead and, if a testcase
+    /// file is supplied, push it to the remote with GDB's host-I/O `remote
+    /// put` before resuming with `continue`. `gdb/triage.py`'s extraction
+    /// works unmodified here since it only touches the `gdb` Python API,
+    /// which is transport-agnostic.
+    fn remote_gdb_args(target: &GdbRemoteTarget, prog_args: &[String], input_file: Option<&str>, triage_script_path: &std::path::Path, syscall_trace: Option<&SyscallTraceConfig>) -> Vec<String> {
+        let mut gdb_args = vec_of_strings!(
+                            "--batch", "--nx",
+                            "-iex", "set index-cache on",
+                            "-iex", "set index-cache directory gdb_cache",
+                            // gdbserver's remote stub carries no debug info of its own;
+                            // load symbols from the local copy of the target binary
+                            "-ex", format!("file {}", prog_args[0]),
+                            "-ex", MARKER_CHILD_OUTPUT.gdb_start,
+                            "-ex", "set logging file /dev/null",
+                            "-ex", "set logging redirect on",
+                            "-ex", "set logging on",
+                            "-ex", target.connect_command());
+
+        if let Some(config) = syscall_trace {
+            gdb_args.extend(Self::syscall_trace_gdb_args(config, triage_script_path));
+        }
+
+        if let Some(file) = input_file {
+            // Assumes the remote stub sees the same path the target expects;
+            // there's no stdin to redirect before a gdbserver attach like
+            // there is for a locally-spawned child.
+            gdb_args.extend(vec_of_strings!("-ex", format!("remote put \"{}\" \"{}\"", file, file)));
+        }
+
+        gdb_args.extend(vec_of_strings!(
+                            "-ex", "continue",
+                            "-ex", "set logging redirect off",
+                            "-ex", "set logging off",
+                            "-ex", MARKER_CHILD_OUTPUT.gdb_end,
+                            "-ex", MARKER_BACKTRACE.gdb_start,
+                            "-x", triage_script_path.to_str().unwrap(),
+                            "-ex", MARKER_BACKTRACE.gdb_end));
+
+        gdb_args
+    }
+
+    fn triage_script_path(&self) -> Result<&std::path::Path, GdbTriageError> {
+        match &self.triage_script {
+            GdbTriageScript::Internal(tf) => Ok(tf.path()),
+            GdbTriageScript::External(p) => Ok(p.as_path()),
+        }
+    }
+
+    /// Spawn a long-lived `GdbSession` that keeps one GDB process alive
+    /// across many testcases instead of relaunching per-testcase like
+    /// `triage_program` does. Callers should fall back to `triage_program`
+    /// if this fails to spawn, and should respawn the session (via this
+    /// method again) if `GdbSession::triage` ever returns an error, since
+    /// that indicates the session's state may be corrupted.
+    pub fn spawn_session(&self, prog_args: &[String], limits: process::ResourceLimits) -> Result<GdbSession, GdbTriageError> {
+        let triage_script_path = self.triage_script_path()?;
+        let rlimit_args = self.rlimit_env_gdb_args(limits);
+
+        GdbSession::spawn(&self.gdb, triage_script_path, prog_args, &rlimit_args, self.syscall_trace.as_ref())
+            .map_err(|e| GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to spawn persistent GDB session", e.to_string()))
+    }
+}
+
+impl Triager for GdbTriager {
+    fn has_supported_debugger(&self) -> bool {
+        self.has_supported_gdb()
+    }
+
+    fn triage_program(
+        &self,
+        prog_args: &[String],
+        input_file: Option<&str>,
+        show_raw_output: bool,
+        timeout_ms: u64,
+        limits: process::ResourceLimits,
+    ) -> Result<GdbTriageResult, GdbTriageError> {
+        GdbTriager::triage_program(self, prog_args, input_file, show_raw_output, timeout_ms, limits)
+    }
+}
+
+/// The JSON body of a `GdbSession` framed reply. Kept distinct from the
+/// batch path's `GdbJsonResult` since the session protocol also reports the
+/// triaged run's child output inline (there's no separate stdout/stderr
+/// stream to scrape markers out of, since the session's own stdout is
+/// reserved for framed replies).
+#[derive(Debug, Serialize, Deserialize)]
+struct GdbSessionReply {
+    result: Option<GdbContextInfo>,
+    #[serde(default)]
+    child_stdout: String,
+    #[serde(default)]
+    child_stderr: String,
+}
+
+/// Block until `fd` is readable or `deadline` passes. Lets a framed read give
+/// up on a wedged/hung GDB session instead of blocking the worker thread on
+/// a `read` that may never return, which is what `ErrorKind::TimedOut` from
+/// `read_framed_reply` actually means.
+fn wait_readable(fd: std::os::unix::io::RawFd, deadline: Instant) -> std::io::Result<()> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(Error::new(ErrorKind::TimedOut, "Timed out waiting for GDB session reply"));
+    }
+
+    let timeout_ms = i32::try_from(remaining.as_millis()).unwrap_or(i32::MAX);
+    let mut fds = [PollFd::new(fd, PollFlags::POLLIN)];
+
+    match poll(&mut fds, timeout_ms) {
+        Ok(0) => Err(Error::new(ErrorKind::TimedOut, "Timed out waiting for GDB session reply")),
+        Ok(_) => Ok(()),
+        Err(nix::errno::Errno::EINTR) => wait_readable(fd, deadline),
+        Err(e) => Err(Error::new(ErrorKind::Other, e.to_string())),
+    }
+}
+
+/// `BufRead::read_line`/`read_exact`, but polling for readability (and
+/// bailing out with `ErrorKind::TimedOut`) before any underlying `read` that
+/// would otherwise block past `deadline`. Only waits when the `BufReader`'s
+/// own buffer is empty, since a read that's already buffered can't block.
+fn read_line_with_deadline<R: BufRead + AsRawFd>(reader: &mut R, deadline: Instant) -> std::io::Result<String> {
+    let mut line = Vec::new();
+
+    loop {
+        if reader.buffer().is_empty() {
+            wait_readable(reader.as_raw_fd(), deadline)?;
+        }
+
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "GDB session closed before sending a reply header"));
+        }
+
+        if line.ends_with(b"\n") {
+            return String::from_utf8(line).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()));
+        }
+    }
+}
+
+fn read_exact_with_deadline<R: BufRead + AsRawFd>(reader: &mut R, buf: &mut [u8], deadline: Instant) -> std::io::Result<()> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        if reader.buffer().is_empty() {
+            wait_readable(reader.as_raw_fd(), deadline)?;
+        }
+
+        let n = reader.read(&mut buf[filled..])?;
+        if n == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "GDB session closed mid-reply"));
+        }
+        filled += n;
+    }
+
+    Ok(())
+}
+
+/// Read one `Content-Length: N\r\n\r\n<body>` framed message off `reader`,
+/// DAP/LSP-style, and return its body. There's no marker scanning and no
+/// ambiguity between the child's own output and the triage reply, since the
+/// length prefix says exactly how many bytes to read. Gives up with
+/// `ErrorKind::TimedOut` if the whole header+body read hasn't completed by
+/// `deadline`, instead of blocking forever on a wedged session.
+fn read_framed_reply<R: BufRead + AsRawFd>(reader: &mut R, deadline: Instant) -> std::io::Result<String> {
+    let mut content_length: Option<usize> = None;
+
+    loop {
+        let line = read_line_with_deadline(reader, deadline)?;
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let content_length = content_length
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "GDB session reply had no Content-Length header"))?;
+
+    let mut body = vec![0u8; content_length];
+    read_exact_with_deadline(reader, &mut body, deadline)?;
+
+    String::from_utf8(body).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+/// A persistent, framed GDB triage session: one `gdb --nx -q` process
+/// kept alive across many testcases, with `gdb/triage.py` loaded once, driven
+/// as a request/response exchange over its stdin/stdout pipes instead of the
+/// marker-scraping relaunch-per-testcase dance `triage_program` does. This
+/// is the transport design DAP-style debugger clients use: a long-lived
+/// child plus length-prefixed message framing.
+pub struct GdbSession {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl GdbSession {
+    fn spawn(gdb: &str, triage_script_path: &std::path::Path, prog_args: &[String], rlimit_args: &[String], syscall_trace: Option<&SyscallTraceConfig>) -> std::io::Result<GdbSession> {
+        let mut gdb_args = vec_of_strings!(
+                            "--nx", "-q",
+                            "-iex", "set index-cache on",
+                            "-iex", "set index-cache directory gdb_cache");
+
+        // Must run before any "run" the session later issues: bounds the
+        // debuggee via `RLIMIT_WRAPPER_SCRIPT`, not GDB's own process.
+        gdb_args.extend_from_slice(rlimit_args);
+
+        // Catchpoints installed here persist across every `run` this
+        // session later issues via `gdbtriage run`, same as `local_gdb_args`
+        // installs them once before its single `run`.
+        if let Some(config) = syscall_trace {
+            gdb_args.extend(GdbTriager::syscall_trace_gdb_args(config, triage_script_path));
+        }
+
+        gdb_args.extend(vec_of_strings!(
+                            "-x", triage_script_path.to_str().unwrap(),
+                            "-ex", "gdbtriage start-session",
+                            "--args"));
+
+        let gdb_cmdline = &[&gdb_args[..], prog_args].concat();
+
+        // `limits` no longer applies to this `Command`: GDB's own process
+        // must not be rlimited (see `rlimit_env_gdb_args`).
+        let mut child = process::spawn_piped(gdb, gdb_cmdline, None)?;
+
+        let stdin = child.stdin.take().ok_or_else(|| Error::new(ErrorKind::Other, "GDB session has no stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| Error::new(ErrorKind::Other, "GDB session has no stdout"))?;
+
+        Ok(GdbSession {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Drive one triage run through the session: send a `gdbtriage run`
+    /// request carrying this testcase's inferior args (mirroring the `run
+    /// {args} < file` line `triage_program` builds for the batch path) and
+    /// read back exactly one framed JSON reply. A `Command` error here means
+    /// the session's state may be corrupted (e.g. the request confused GDB
+    /// or the Python side crashed); the caller should `kill` this session
+    /// and fall back to `triage_program` for this testcase, then respawn a
+    /// fresh session for subsequent ones.
+    pub fn triage(&mut self, prog_args: &[String], input_file: Option<&str>, timeout_ms: u64) -> Result<GdbTriageResult, GdbTriageError> {
+        // GDB overwrites args in the format (damn you), same caveat as triage_program
+        let run_args = match input_file {
+            Some(file) => format!("{} < \"{}\"", &prog_args[1..].join(" "), file),
+            None => prog_args[1..].join(" "),
+        };
+
+        let request = format!("gdbtriage run {}\n", run_args);
+
+        if let Err(e) = self.stdin.write_all(request.as_bytes()).and_then(|_| self.stdin.flush()) {
+            return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to send request to GDB session", e.to_string()));
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+
+        let body = match read_framed_reply(&mut self.stdout, deadline) {
+            Ok(body) => body,
+            Err(e) => {
+                return if e.kind() == ErrorKind::TimedOut {
+                    Err(GdbTriageError::new(GdbTriageErrorKind::Timeout, "Timed out waiting for GDB session reply", e.to_string()))
+                } else {
+                    Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to read GDB session reply", e.to_string()))
+                };
+            }
+        };
+
+        let reply: GdbSessionReply = serde_json::from_str(&body)
+            .map_err(|e| GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to parse GDB session reply", e.to_string()))?;
+
+        // The session has no real per-testcase child process to wait on, so
+        // there's no exit status to report; nothing downstream reads it.
+        let status = std::os::unix::process::ExitStatusExt::from_raw(0);
+
+        Ok(GdbTriageResult {
+            response: GdbJsonResult { result: reply.result },
+            child: ChildResult {
+                stdout: reply.child_stdout,
+                stderr: reply.child_stderr,
+                status,
+            },
+        })
+    }
+
+    /// Tear down the session's GDB process. Called by the caller once a
+    /// session is no longer trusted (a `triage` error) or is being retired.
+    pub fn kill(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
 }
@@ -0,0 +1,255 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+//
+// An LLDB-backed `Triager`, for macOS and any toolchain where LLDB is the
+// only available debugger (or simply has better symbol/register coverage
+// than GDB for a given target). Drives `lldb` the same way `GdbTriager`
+// drives `gdb`: relaunch once per testcase in batch mode, load a Python
+// triage script, and recover its JSON output by scanning for text markers
+// written to both stdout and stderr. It emits the same `GdbJsonResult`/
+// `GdbContextInfo` schema GDB does, so everything downstream of
+// `triage_program` (severity classification, report formatting, rules,
+// clustering) is unaware of which debugger actually ran.
+use std::io::Write;
+use std::path::PathBuf;
+
+use tempfile;
+
+use crate::gdb_triage::{GdbTriageError, GdbTriageErrorKind, GdbTriageResult, GdbJsonResult, Triager};
+use crate::process::{self, ChildResult};
+
+const INTERNAL_TRIAGE_SCRIPT: &[u8] = include_bytes!("../lldb/triage.py");
+
+struct DbgMarker {
+    start: &'static str,
+    end: &'static str,
+}
+
+impl DbgMarker {
+    fn extract<'a>(&self, text: &'a str) -> Result<&'a str, String> {
+        match text.find(self.start) {
+            Some(mut start_idx) => match text.find(self.end) {
+                Some(end_idx) => {
+                    start_idx += self.start.len() + 1;
+
+                    if start_idx <= end_idx {
+                        Ok(&text[start_idx..end_idx])
+                    } else {
+                        Err(String::from("Start marker and end marker out-of-order"))
+                    }
+                }
+                None => Err(format!("Could not find {}", self.end)),
+            },
+            None => Err(format!("Could not find {}", self.start)),
+        }
+    }
+
+    // LLDB's Python scripting API has no logging redirect like GDB's "set
+    // logging"; write the marker to both stdout and stderr ourselves via a
+    // one-line `script` command so child output and triage JSON stay
+    // separable, same trick `gdb_triage::DbgMarker` uses.
+    fn lldb_command(marker: &'static str) -> String {
+        format!("script import sys; [(x.write('{}\\n'), x.flush()) for x in [sys.stdout, sys.stderr]]", marker)
+    }
+}
+
+lazy_static! {
+    static ref MARKER_CHILD_OUTPUT: DbgMarker = DbgMarker {
+        start: "----AFLTRIAGE_LLDB_CHILD_OUTPUT_START----",
+        end: "----AFLTRIAGE_LLDB_CHILD_OUTPUT_END----",
+    };
+    static ref MARKER_BACKTRACE: DbgMarker = DbgMarker {
+        start: "----AFLTRIAGE_LLDB_BACKTRACE_START----",
+        end: "----AFLTRIAGE_LLDB_BACKTRACE_END----",
+    };
+}
+
+enum LldbTriageScript {
+    External(PathBuf),
+    Internal(tempfile::NamedTempFile),
+}
+
+pub struct LldbTriager {
+    triage_script: LldbTriageScript,
+    lldb: String,
+    rlimit_wrapper: tempfile::NamedTempFile,
+}
+
+impl LldbTriager {
+    pub fn new() -> LldbTriager {
+        let mut triage_script =
+            LldbTriageScript::Internal(tempfile::Builder::new().suffix(".py").tempfile().unwrap());
+
+        if let LldbTriageScript::Internal(ref mut tf) = triage_script {
+            tf.write_all(INTERNAL_TRIAGE_SCRIPT).unwrap();
+        }
+
+        let rlimit_wrapper = process::write_rlimit_wrapper();
+
+        LldbTriager { triage_script, lldb: "lldb".to_string(), rlimit_wrapper }
+    }
+
+    pub fn has_supported_lldb(&self) -> bool {
+        let python_cmd = "script import lldb, sys; print('V:'+lldb.SBDebugger.GetVersionString()); print('P:'+sys.version.splitlines()[0].strip())";
+        let lldb_args = vec!["-b", "-O", &python_cmd];
+
+        let output = match process::execute_capture_output(&self.lldb, &lldb_args) {
+            Ok(o) => o,
+            Err(e) => {
+                log::error!("Failed to execute '{}': {}", &self.lldb, e);
+                return false;
+            }
+        };
+
+        let decoded_stdout = &output.stdout;
+        let decoded_stderr = &output.stderr;
+
+        let version = decoded_stdout
+            .find("V:")
+            .map(|start_idx| decoded_stdout[start_idx + 2..].lines().next().unwrap());
+        let python_version = decoded_stdout
+            .find("P:")
+            .map(|start_idx| decoded_stdout[start_idx + 2..].lines().next().unwrap());
+
+        if !output.status.success() || version.is_none() || python_version.is_none() {
+            log::error!(
+                "LLDB sanity check failure\nARGS:{}\nSTDOUT: {}\nSTDERR: {}",
+                lldb_args.join(" "), decoded_stdout, decoded_stderr
+            );
+            return false;
+        }
+
+        log::info!("LLDB is working ({} - Python {})", version.unwrap(), python_version.unwrap());
+
+        true
+    }
+
+    fn triage_program_impl(
+        &self,
+        prog_args: &[String],
+        input_file: Option<&str>,
+        show_raw_output: bool,
+        timeout_ms: u64,
+        limits: process::ResourceLimits,
+    ) -> Result<GdbTriageResult, GdbTriageError> {
+        let triage_script_path = match &self.triage_script {
+            LldbTriageScript::Internal(tf) => tf.path(),
+            LldbTriageScript::External(p) => p.as_path(),
+        };
+
+        let run_command = match input_file {
+            Some(file) => format!("process launch -i {} --", file),
+            None => "process launch --".to_string(),
+        };
+
+        let mut lldb_args = vec![
+            "-b".to_string(),
+            "-O".to_string(), "command script import ".to_string() + triage_script_path.to_str().unwrap(),
+        ];
+
+        // Same fix as `gdb_triage::GdbTriager::rlimit_env_gdb_args`:
+        // `setrlimit` persists across `exec`, so these must land on the
+        // debuggee, not on LLDB's own process. `target create` is pointed
+        // at `RLIMIT_WRAPPER_SCRIPT` instead of the real binary, and it's
+        // launched with the real `prog_args` (including `prog_args[0]`) so
+        // its `exec "$@"` re-execs into the actual target, inheriting the
+        // limits it applied to itself first.
+        let rlimit_env_vars = process::rlimit_env_vars(limits);
+        if !rlimit_env_vars.is_empty() {
+            let assignments = rlimit_env_vars.iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>().join(" ");
+            lldb_args.extend(vec!["-o".to_string(), format!("settings set target.env-vars {}", assignments)]);
+        }
+
+        lldb_args.extend(vec![
+            "-o".to_string(), DbgMarker::lldb_command(MARKER_CHILD_OUTPUT.start),
+            "-o".to_string(), format!("target create {}", self.rlimit_wrapper.path().to_str().unwrap()),
+            "-o".to_string(), format!("{} {}", run_command, prog_args.join(" ")),
+            "-o".to_string(), DbgMarker::lldb_command(MARKER_CHILD_OUTPUT.end),
+            "-o".to_string(), DbgMarker::lldb_command(MARKER_BACKTRACE.start),
+            "-o".to_string(), "script aflTriage()".to_string(),
+            "-o".to_string(), DbgMarker::lldb_command(MARKER_BACKTRACE.end),
+        ]);
+
+        let lldb_cmd_fmt = [std::slice::from_ref(&self.lldb), &lldb_args[..]].concat().join(" ");
+
+        // `limits` no longer applies to this `Command`: LLDB's own process
+        // must not be rlimited (see the `target create`/`target.env-vars`
+        // setup above).
+        let output = match process::execute_capture_output_timeout(&self.lldb, &lldb_args, timeout_ms, None, None) {
+            Ok(o) => o,
+            Err(e) => {
+                return if e.kind() == std::io::ErrorKind::TimedOut {
+                    Err(GdbTriageError::new(GdbTriageErrorKind::Timeout, "Timed out when triaging", e.to_string()))
+                } else {
+                    Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to execute LLDB command", e.to_string()))
+                };
+            }
+        };
+
+        let decoded_stdout = &output.stdout;
+        let decoded_stderr = &output.stderr;
+
+        if show_raw_output {
+            println!(
+                "--- RAW LLDB BEGIN ---\nPROGRAM CMDLINE: {}\nLLDB CMDLINE: {}\nSTDOUT:\n{}\nSTDERR:\n{}\n--- RAW LLDB END ---",
+                prog_args[..].join(" "), lldb_cmd_fmt, decoded_stdout, decoded_stderr
+            );
+        }
+
+        let child_output_stdout = match MARKER_CHILD_OUTPUT.extract(decoded_stdout) {
+            Ok(output) => output.to_string(),
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Could not extract child STDOUT", e)),
+        };
+
+        let child_output_stderr = match MARKER_CHILD_OUTPUT.extract(decoded_stderr) {
+            Ok(output) => output.to_string(),
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Could not extract child STDERR", e)),
+        };
+
+        let backtrace_output = match MARKER_BACKTRACE.extract(decoded_stdout) {
+            Ok(output) => output,
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to get triage JSON from LLDB", e)),
+        };
+
+        let backtrace_messages = match MARKER_BACKTRACE.extract(decoded_stderr) {
+            Ok(output) => output,
+            Err(e) => return Err(GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to get triage errors from LLDB", e)),
+        };
+
+        if backtrace_output.is_empty() && !backtrace_messages.is_empty() {
+            return Err(GdbTriageError::new_detailed(
+                GdbTriageErrorKind::Command,
+                "Triage script emitted errors",
+                backtrace_messages.lines().map(str::to_string).collect(),
+            ));
+        }
+
+        let response: GdbJsonResult = serde_json::from_str(backtrace_output)
+            .map_err(|e| GdbTriageError::new(GdbTriageErrorKind::Command, "Failed to parse triage JSON from LLDB", e.to_string()))?;
+
+        Ok(GdbTriageResult {
+            response,
+            child: ChildResult { stdout: child_output_stdout, stderr: child_output_stderr, status: output.status },
+        })
+    }
+}
+
+impl Triager for LldbTriager {
+    fn has_supported_debugger(&self) -> bool {
+        self.has_supported_lldb()
+    }
+
+    fn triage_program(
+        &self,
+        prog_args: &[String],
+        input_file: Option<&str>,
+        show_raw_output: bool,
+        timeout_ms: u64,
+        limits: process::ResourceLimits,
+    ) -> Result<GdbTriageResult, GdbTriageError> {
+        self.triage_program_impl(prog_args, input_file, show_raw_output, timeout_ms, limits)
+    }
+}
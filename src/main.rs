@@ -2,14 +2,16 @@
 //
 // SPDX-License-Identifier: BSD-3-Clause
 use clap::{arg_enum, App, AppSettings, Arg, ArgMatches};
+use crossbeam_channel::unbounded;
 use indicatif::{ProgressBar, ProgressStyle};
 use is_executable::IsExecutable;
 use rayon::prelude::*;
 use regex::Regex;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::env;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
 #[macro_use]
@@ -20,14 +22,23 @@ extern crate clap;
 extern crate num_cpus;
 
 pub mod afl;
+pub mod cluster;
 pub mod gdb_triage;
+pub mod lldb_triage;
 pub mod platform;
 pub mod process;
 pub mod report;
+pub mod rules;
+pub mod sanitizer;
+pub mod severity;
+pub mod stackhash;
 pub mod util;
 
-use gdb_triage::{GdbTriageError, GdbTriageResult, GdbTriager};
+use gdb_triage::{GdbSession, GdbTriageError, GdbTriageResult, GdbTriager, Triager};
+use lldb_triage::LldbTriager;
 use process::ChildResult;
+use sanitizer::SanitizerSelector;
+use severity::Severity;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -43,6 +54,59 @@ arg_enum! {
     }
 }
 
+arg_enum! {
+    #[derive(PartialEq, Debug)]
+
+    // these are user facing
+    #[allow(non_camel_case_types)]
+    pub enum DebuggerKind {
+        gdb,
+        lldb
+    }
+}
+
+/// Which concrete `Triager` backs a triage run, dispatched on `--debugger`.
+/// GDB is still the only backend with the persistent-session
+/// (`GdbSession`) and remote (`GdbRemoteTarget`) fast paths, so callers that
+/// want those should match out the `Gdb` variant rather than only going
+/// through the `Triager` trait.
+enum DebuggerBackend {
+    Gdb(GdbTriager),
+    Lldb(LldbTriager),
+}
+
+impl DebuggerBackend {
+    fn as_gdb(&self) -> Option<&GdbTriager> {
+        match self {
+            DebuggerBackend::Gdb(gdb) => Some(gdb),
+            DebuggerBackend::Lldb(_) => None,
+        }
+    }
+}
+
+impl Triager for DebuggerBackend {
+    fn has_supported_debugger(&self) -> bool {
+        match self {
+            DebuggerBackend::Gdb(gdb) => gdb.has_supported_debugger(),
+            DebuggerBackend::Lldb(lldb) => lldb.has_supported_debugger(),
+        }
+    }
+
+    fn triage_program(
+        &self,
+        prog_args: &[String],
+        input_file: Option<&str>,
+        show_raw_output: bool,
+        timeout_ms: u64,
+        limits: process::ResourceLimits,
+    ) -> Result<GdbTriageResult, GdbTriageError> {
+        match self {
+            DebuggerBackend::Gdb(gdb) => gdb.triage_program(prog_args, input_file, show_raw_output, timeout_ms, limits),
+            DebuggerBackend::Lldb(lldb) => lldb.triage_program(prog_args, input_file, show_raw_output, timeout_ms, limits),
+        }
+    }
+}
+
 fn setup_command_line() -> ArgMatches<'static> {
     let mut app = App::new("afltriage")
                           .version(crate_version!())
@@ -105,15 +169,92 @@ fn setup_command_line() -> ArgMatches<'static> {
                                .default_value("text")
                                .required(false)
                                .case_insensitive(true)
-                               .help("The triage report output format."))
+                               .help("The triage report output format. \"json\" emits one structured JSON object per crash (a JSONL stream when -o is '-')."))
+                          .arg(Arg::with_name("major_hash_frames")
+                               .long("--major-hash-frames")
+                               .takes_value(true)
+                               .default_value("5")
+                               .help("How many meaningful (non-skipped) backtrace frames to include in the major stack hash used for top-level crash dedup."))
+                          .arg(Arg::with_name("frame_skip_regex")
+                               .long("--frame-skip-regex")
+                               .takes_value(true)
+                               .help("A regex matching normalized frame identifiers (function@file) to skip when computing the major hash, e.g. libc/ASAN runtime frames. Defaults to a built-in list."))
+                          .arg(Arg::with_name("cluster_threshold")
+                               .long("--cluster-threshold")
+                               .takes_value(true)
+                               .default_value("0.3")
+                               .help("Fuzzy-cluster unique crashes whose normalized-LCS stack trace distance is below this threshold (0.0 = identical, 1.0 = no shared frames) into shared cluster_000N/ subdirectories."))
+                          .arg(Arg::with_name("rules_file")
+                               .long("--rules-file")
+                               .takes_value(true)
+                               .help("A TOML or JSON file of rules that match on a crash's headline, backtrace, ASAN/UBSAN body, or crashing function to tag, override the severity of, route, or suppress its report. See rules.rs for the schema."))
+                          .arg(Arg::with_name("bind_cpus")
+                               .long("--bind-cpus")
+                               .takes_value(false)
+                               .help("Pin each triage worker thread to a distinct CPU core (Linux/FreeBSD only). Reduces scheduler contention and stabilizes per-testcase timing."))
+                          .arg(Arg::with_name("rlimit_as")
+                               .long("--rlimit-as")
+                               .takes_value(true)
+                               .help("Limit the debugged child's virtual address space, in bytes (RLIMIT_AS). Unset by default."))
+                          .arg(Arg::with_name("rlimit_cpu")
+                               .long("--rlimit-cpu")
+                               .takes_value(true)
+                               .help("Limit the debugged child's CPU time, in seconds (RLIMIT_CPU). Unset by default."))
+                          .arg(Arg::with_name("rlimit_core")
+                               .long("--rlimit-core")
+                               .takes_value(true)
+                               .default_value("0")
+                               .help("Limit the size of core dumps the debugged child may write, in bytes (RLIMIT_CORE). Defaults to 0 so triage never litters core files."))
+                          .arg(Arg::with_name("rlimit_nofile")
+                               .long("--rlimit-nofile")
+                               .takes_value(true)
+                               .help("Limit the number of open file descriptors the debugged child may hold (RLIMIT_NOFILE). Unset by default."))
+                          .arg(Arg::with_name("sanitizer")
+                               .long("--sanitizer")
+                               .takes_value(true)
+                               .possible_values(&SanitizerSelector::variants())
+                               .default_value("both")
+                               .case_insensitive(true)
+                               .help("Which sanitizer(s) the target was built with, so AFLTriage can configure their options and parse their diagnostics."))
                           .arg(Arg::with_name("stdin")
                                .long("--stdin")
                                .takes_value(false)
                                .help("Provide testcase input to the target via stdin instead of a file."))
+                          .arg(Arg::with_name("debugger")
+                               .long("--debugger")
+                               .takes_value(true)
+                               .possible_values(&DebuggerKind::variants())
+                               .default_value("gdb")
+                               .case_insensitive(true)
+                               .help("Which debugger backend to triage with. \"lldb\" is useful on macOS and toolchains without a usable GDB, or where LLDB has better symbol/register coverage for the target; --gdb-remote and the persistent-session fast path are GDB-only."))
+                          .arg(Arg::with_name("gdb_remote")
+                               .long("--gdb-remote")
+                               .takes_value(true)
+                               .value_name("HOST:PORT")
+                               .help("Triage against a gdbserver already running at HOST:PORT instead of spawning the target locally. The command/binary is still needed (for symbols) but is no longer spawned by AFLTriage."))
+                          .arg(Arg::with_name("gdb_remote_extended")
+                               .long("--gdb-remote-extended")
+                               .takes_value(false)
+                               .requires("gdb_remote")
+                               .help("Use \"target extended-remote\" instead of \"target remote\" when connecting to --gdb-remote."))
+                          .arg(Arg::with_name("trace_syscalls")
+                               .long("--trace-syscalls")
+                               .takes_value(true)
+                               .value_name("SYSCALL,...|all")
+                               .min_values(0)
+                               .require_equals(true)
+                               .help("GDB-only. Install \"catch syscall\" catchpoints and record a trailing trace of the process's syscalls (name, argument registers, return value) alongside the crash context, bounded to the last --trace-syscalls-ring-size hits. Pass a comma-separated allow-list (e.g. open,read,write,mmap,execve) or \"all\"; defaults to \"all\" if given with no value."))
+                          .arg(Arg::with_name("trace_syscalls_ring_size")
+                               .long("--trace-syscalls-ring-size")
+                               .takes_value(true)
+                               .requires("trace_syscalls")
+                               .default_value("32")
+                               .help("How many of the most recent syscall catchpoint hits to keep in the report when --trace-syscalls is set."))
                           .arg(Arg::with_name("command")
                                .multiple(true)
-                               .required(true)
-                               .help("The binary executable and args to execute. Use '@@' as a placeholder for the path to the input file or --stdin. Optionally use -- to delimit the start of the command."));
+                               .required(false)
+                               .help("The binary executable and args to execute. Use '@@' as a placeholder for the path to the input file or --stdin. Optionally use -- to delimit the start of the command. \
+                                     May be omitted if every -i input is an AFL directory, in which case the command is parsed out of its fuzzer_stats."));
 
     if env::args().len() <= 1 {
         app.print_help().unwrap();
@@ -124,13 +265,32 @@ fn setup_command_line() -> ArgMatches<'static> {
     app.get_matches()
 }
 
+/// A unique crash's rendered report, kept around (instead of written
+/// immediately) so it can be placed into its `cluster_000N/` subdirectory
+/// after fuzzy clustering runs in the final, sequential pass.
+struct CrashRecord {
+    trace: Vec<String>,
+    filename: String,
+    text_report: String,
+    /// Subdirectory (relative to the output dir) a rule routed this report
+    /// to, if any. Routed reports skip fuzzy clustering entirely.
+    route: Option<String>,
+}
+
 struct TriageState {
     crashed: usize,
     no_crash: usize,
     timedout: usize,
     errored: usize,
-    crash_signature: HashSet<String>,
+    /// Major hash -> set of minor hashes seen under that bucket.
+    crash_buckets: HashMap<String, HashSet<String>>,
     unique_errors: HashMap<GdbTriageError, usize>,
+    severity_counts: HashMap<Severity, usize>,
+    crash_records: Vec<CrashRecord>,
+    /// Count of crashes tagged by each rule, keyed by tag label.
+    tag_counts: HashMap<String, usize>,
+    /// Count of crashes a rule suppressed (counted, but no report written).
+    suppressed: usize,
 }
 
 enum TriageResult {
@@ -140,6 +300,27 @@ enum TriageResult {
     Timedout,
 }
 
+/// What a rayon worker hands off to the aggregator thread once a testcase has
+/// been triaged. Workers do the independent, per-testcase work (running the
+/// debugger, rendering the report, evaluating rules); the aggregator owns
+/// `TriageState` and is the only thing that touches dedup/clustering
+/// bookkeeping, the progress bar, and report files, so there's no lock to
+/// contend over on the hot path.
+enum TriageEvent {
+    NoCrash { path: String },
+    Timedout { path: String },
+    Error {
+        path: String,
+        error: GdbTriageError,
+    },
+    Crash {
+        path: String,
+        triage: GdbTriageResult,
+        report: report::TriageReport,
+        verdicts: Vec<(String, rules::RuleVerdict)>,
+    },
+}
+
 struct ProfileResult {
     process_result: std::io::Result<ChildResult>,
     process_execution_time: Duration,
@@ -152,12 +333,13 @@ struct ProfileResult {
 }
 
 fn profile_target(
-    gdb: &GdbTriager,
+    gdb: &DebuggerBackend,
     binary_args: &[&str],
     testcase: &str,
     debug: bool,
     input_stdin: bool,
     timeout_ms: u64,
+    limits: process::ResourceLimits,
 ) -> std::io::Result<ProfileResult> {
     log::info!("Profiling target...");
 
@@ -171,7 +353,7 @@ fn profile_target(
 
     let start = Instant::now();
     let before_rss = util::get_peak_rss();
-    let process_result = process::execute_capture_output_timeout(&prog_args[0], &prog_args[1..], timeout_ms, input_file);
+    let process_result = process::execute_capture_output_timeout(&prog_args[0], &prog_args[1..], timeout_ms, input_file, Some(limits));
     let process_execution_time = start.elapsed();
     let after_process_rss = util::get_peak_rss();
     let process_rss = std::cmp::max(after_process_rss - before_rss, 1); // round up to 1kb
@@ -180,7 +362,7 @@ fn profile_target(
         process_execution_time, process_rss);
 
     let start = Instant::now();
-    let triage_result = triage_test_case(gdb, binary_args, testcase, debug, input_stdin, timeout_ms);
+    let triage_result = triage_test_case(gdb, binary_args, testcase, debug, input_stdin, timeout_ms, limits);
     let debugger_execution_time = start.elapsed();
     let after_debugger_rss = util::get_peak_rss();
 
@@ -207,6 +389,38 @@ fn profile_target(
     })
 }
 
+/// Parse a `--gdb-remote HOST:PORT` value. Split on the last `:` so
+/// bracket-free IPv6 hosts aren't a concern here (gdbserver's own listen
+/// address is almost always a plain IPv4 host or hostname).
+fn parse_gdb_remote(hostport: &str) -> Result<(String, u16), String> {
+    let (host, port) = hostport
+        .rsplit_once(':')
+        .ok_or_else(|| "expected HOST:PORT".to_string())?;
+
+    let port = port
+        .parse::<u16>()
+        .map_err(|e| format!("invalid port \"{}\": {}", port, e))?;
+
+    if host.is_empty() {
+        return Err("host must not be empty".to_string());
+    }
+
+    Ok((host.to_string(), port))
+}
+
+/// Parse a `--trace-syscalls` value into a `SyscallFilter`. `""`/`"all"`
+/// (case-insensitive) installs a catchpoint for every syscall; anything else
+/// is treated as a comma-separated allow-list.
+fn parse_syscall_filter(value: &str) -> gdb_triage::SyscallFilter {
+    let value = value.trim();
+
+    if value.is_empty() || value.eq_ignore_ascii_case("all") {
+        gdb_triage::SyscallFilter::All
+    } else {
+        gdb_triage::SyscallFilter::Named(value.split(',').map(str::trim).map(String::from).collect())
+    }
+}
+
 fn expand_filepath_templates(args: &[&str], value: &str) -> Vec<String> {
     let mut expanded_args: Vec<String> = Vec::new();
 
@@ -221,21 +435,83 @@ fn expand_filepath_templates(args: &[&str], value: &str) -> Vec<String> {
     expanded_args
 }
 
-fn triage_test_case(
+thread_local! {
+    // Each rayon worker thread keeps its own persistent GDB session (debug
+    // mode always wants the raw per-testcase batch output, so it never uses
+    // one) and reuses it across every testcase that thread handles, rather
+    // than paying a fresh `gdb --nx --batch` relaunch per testcase.
+    static GDB_SESSION: RefCell<Option<GdbSession>> = RefCell::new(None);
+}
+
+// `GdbSession::triage` polls its reply pipe with the same per-testcase
+// `timeout_ms` the batch path's `execute_capture_output_timeout` uses; a
+// wedged request surfaces as a `GdbTriageErrorKind::Timeout` directly,
+// instead of blocking the worker thread forever.
+fn triage_via_session(
     gdb: &GdbTriager,
+    prog_args: &[String],
+    input_file: Option<&str>,
+    timeout_ms: u64,
+    limits: process::ResourceLimits,
+) -> Option<Result<GdbTriageResult, GdbTriageError>> {
+    GDB_SESSION.with(|cell| {
+        let mut slot = cell.borrow_mut();
+
+        if slot.is_none() {
+            match gdb.spawn_session(prog_args, limits) {
+                Ok(session) => *slot = Some(session),
+                Err(e) => {
+                    log::warn!("Failed to spawn persistent GDB session, falling back to batch triage: {}", e.error);
+                    return None;
+                }
+            }
+        }
+
+        let result = slot.as_mut().unwrap().triage(prog_args, input_file, timeout_ms);
+
+        // A parse error or timeout may have left the inferior or GDB itself
+        // wedged, or the request/reply stream desynced; respawning on the
+        // next testcase is cheaper than trusting a session in that state.
+        if result.is_err() {
+            if let Some(session) = slot.take() {
+                session.kill();
+            }
+        }
+
+        Some(result)
+    })
+}
+
+fn triage_test_case(
+    debugger: &DebuggerBackend,
     binary_args: &[&str],
     testcase: &str,
     debug: bool,
     input_stdin: bool,
     timeout_ms: u64,
+    limits: process::ResourceLimits,
 ) -> TriageResult {
     let prog_args = expand_filepath_templates(binary_args, testcase);
 
     // Whether to pass a file in via GDB stdin
     let input_file = if input_stdin { Some(testcase) } else { None };
 
-    let triage_result: GdbTriageResult =
-        match gdb.triage_program(&prog_args, input_file, debug, timeout_ms) {
+    // `show_raw_output` (debug) wants the unadorned batch GDB transcript, a
+    // remote target has no local `--args` child for `GdbSession` to spawn,
+    // and the persistent session is a GDB-only fast path, so skip it in any
+    // of those cases. Otherwise use this thread's session if one's alive
+    // (or can be spawned); its errors have already torn the session down by
+    // the time they reach us here, so on `None` (no usable session) or
+    // `Some(Err(_))` (session request failed) we fall back to a one-off
+    // batch triage for this testcase.
+    let via_session = match debugger.as_gdb() {
+        Some(gdb) if !debug && !gdb.is_remote() => triage_via_session(gdb, &prog_args, input_file, timeout_ms, limits),
+        _ => None,
+    };
+
+    let triage_result: GdbTriageResult = match via_session {
+        Some(Ok(triage_result)) => triage_result,
+        _ => match debugger.triage_program(&prog_args, input_file, debug, timeout_ms, limits) {
             Ok(triage_result) => triage_result,
             Err(e) => {
                 if e.error_kind == gdb_triage::GdbTriageErrorKind::Timeout {
@@ -244,7 +520,8 @@ fn triage_test_case(
                     return TriageResult::Error(e);
                 }
             }
-        };
+        },
+    };
 
     if triage_result.response.result.is_none() {
         TriageResult::NoCrash(triage_result.child)
@@ -299,7 +576,7 @@ fn determine_input_type(input: &Path) -> UserInputPathType {
     UserInputPathType::Unknown
 }
 
-fn sanity_check(gdb: &GdbTriager, binary_args: &[&str]) -> bool {
+fn sanity_check(debugger: &dyn Triager, binary_args: &[&str], sanitizer: SanitizerSelector) -> bool {
     let rawexe = binary_args.get(0).unwrap();
     let exe = PathBuf::from(rawexe);
     let justfilename = exe
@@ -322,7 +599,7 @@ fn sanity_check(gdb: &GdbTriager, binary_args: &[&str]) -> bool {
         return false;
     }
 
-    if !gdb.has_supported_gdb() {
+    if !debugger.has_supported_debugger() {
         return false;
     }
 
@@ -330,43 +607,110 @@ fn sanity_check(gdb: &GdbTriager, binary_args: &[&str]) -> bool {
     // https://stackoverflow.com/questions/32056387/catching-libc-error-messages-redirecting-from-dev-tty
     env::set_var("LIBC_FATAL_STDERR_", "1");
 
-    match env::var("ASAN_OPTIONS") {
-        Ok(val) => {
-            log::warn!("Using ASAN_OPTIONS=\"{}\" that was set by the environment. This can change triage result accuracy", val);
+    if sanitizer.wants_asan() {
+        match env::var("ASAN_OPTIONS") {
+            Ok(val) => {
+                log::warn!("Using ASAN_OPTIONS=\"{}\" that was set by the environment. This can change triage result accuracy", val);
 
-            let re = Regex::new(r"abort_on_error=(1|true)").unwrap();
-            if re.find(&val).is_none() {
-                log::error!("ASAN_OPTIONS does not have required abort_on_error=1 option");
-                return false;
+                let re = Regex::new(r"abort_on_error=(1|true)").unwrap();
+                if re.find(&val).is_none() {
+                    log::error!("ASAN_OPTIONS does not have required abort_on_error=1 option");
+                    return false;
+                }
             }
+            Err(_) => env::set_var(
+                "ASAN_OPTIONS",
+                "abort_on_error=1:allow_user_segv_handler=0:symbolize=1,detect_leaks=0",
+            ),
         }
-        Err(_) => env::set_var(
-            "ASAN_OPTIONS",
-            "abort_on_error=1:allow_user_segv_handler=0:symbolize=1,detect_leaks=0",
-        ),
-    }
 
-    match env::var("ASAN_SYMBOLIZER_PATH") {
-        Ok(val) => {
-            log::info!(
-                "Using ASAN_SYMBOLIZER_PATH=\"{}\" that was set by the environment",
-                val
-            );
-        }
-        Err(_) => match which::which("addr2line") {
-            Ok(path) => {
-                env::set_var("ASAN_SYMBOLIZER_PATH", path.to_str().unwrap());
-                log::info!("Using ASAN_SYMBOLIZER_PATH=\"{}\"", path.to_str().unwrap());
+        match env::var("ASAN_SYMBOLIZER_PATH") {
+            Ok(val) => {
+                log::info!(
+                    "Using ASAN_SYMBOLIZER_PATH=\"{}\" that was set by the environment",
+                    val
+                );
             }
-            _ => {
-                log::warn!("No ASAN_SYMBOLIZER_PATH found. Consider setting it to llvm-symbolizer or addr2line if your target is using ASAN");
+            Err(_) => match which::which("addr2line") {
+                Ok(path) => {
+                    env::set_var("ASAN_SYMBOLIZER_PATH", path.to_str().unwrap());
+                    log::info!("Using ASAN_SYMBOLIZER_PATH=\"{}\"", path.to_str().unwrap());
+                }
+                _ => {
+                    log::warn!("No ASAN_SYMBOLIZER_PATH found. Consider setting it to llvm-symbolizer or addr2line if your target is using ASAN");
+                }
+            },
+        }
+    }
+
+    if sanitizer.wants_ubsan() {
+        match env::var("UBSAN_OPTIONS") {
+            Ok(val) => {
+                log::warn!("Using UBSAN_OPTIONS=\"{}\" that was set by the environment. This can change triage result accuracy", val);
+
+                let re = Regex::new(r"halt_on_error=(1|true)").unwrap();
+                if re.find(&val).is_none() {
+                    log::error!("UBSAN_OPTIONS does not have required halt_on_error=1 option");
+                    return false;
+                }
             }
-        },
+            Err(_) => env::set_var(
+                "UBSAN_OPTIONS",
+                "print_stacktrace=1:halt_on_error=1:abort_on_error=1",
+            ),
+        }
     }
 
     true
 }
 
+/// When the user omits the trailing `command`, try to recover it from the
+/// `command_line` recorded in each AFL directory's `fuzzer_stats`, the way
+/// casr-afl reconstructs `target_args`/`at_index` from the same file. Every
+/// input must be an AFL directory and they must all agree on the command
+/// line. Returns the parsed argv and whether the target reads its testcase
+/// from stdin (i.e. the recorded command line has no `@@` placeholder).
+fn derive_command_from_afl_dirs(input_paths: &[&str]) -> Result<(Vec<String>, bool), String> {
+    let mut derived: Option<(String, Vec<String>)> = None;
+
+    for input in input_paths {
+        let path = PathBuf::from(input);
+
+        if !matches!(determine_input_type(&path), UserInputPathType::AflDir) {
+            return Err(format!(
+                "No command was given and \"{}\" is not an AFL directory. \
+                 Either pass a command explicitly or use only AFL directories with fuzzer_stats.",
+                input
+            ));
+        }
+
+        let stats = afl::parse_afl_fuzzer_stats(path.join("fuzzer_stats").as_path())
+            .map_err(|e| format!("Failed to read fuzzer_stats for \"{}\": {}", input, e))?;
+        let stats = afl::validate_afl_fuzzer_stats(&stats)
+            .map_err(|e| format!("Failed to validate fuzzer_stats for \"{}\": {}", input, e))?;
+
+        let argv = shell_words::split(&stats.command_line)
+            .map_err(|e| format!("Failed to parse command_line from \"{}\": {}", input, e))?;
+
+        match &derived {
+            None => derived = Some((stats.command_line.clone(), argv)),
+            Some((prev_cmdline, _)) if *prev_cmdline != stats.command_line => {
+                return Err(format!(
+                    "AFL directories disagree on their target command line (\"{}\" vs \"{}\"). \
+                     Triage each fuzzer separately or pass the command explicitly.",
+                    prev_cmdline, stats.command_line
+                ));
+            }
+            Some(_) => (),
+        }
+    }
+
+    let (_, argv) = derived.ok_or_else(|| "No inputs were given".to_string())?;
+    let use_stdin = !argv.iter().any(|a| a == "@@");
+
+    Ok((argv, use_stdin))
+}
+
 fn collect_input_testcases(processed_inputs: &mut Vec<UserInputPath>) -> Vec<Testcase> {
     let mut all_testcases = Vec::new();
 
@@ -524,16 +868,86 @@ fn main_wrapper() -> i32 {
     println!("AFLTriage v{} by Grant Hernandez\n", VERSION);
     init_logger();
 
-    let binary_args: Vec<&str> = args.values_of("command").unwrap().collect();
+    let input_paths: Vec<&str> = args.values_of("input").unwrap().collect();
+
+    let (binary_args_owned, derived_stdin) = match args.values_of("command") {
+        Some(cmd) => (cmd.map(str::to_string).collect(), None),
+        None => match derive_command_from_afl_dirs(&input_paths) {
+            Ok((argv, use_stdin)) => {
+                log::info!(
+                    "No command given - derived target command line from AFL fuzzer_stats: \"{}\"",
+                    argv.join(" ")
+                );
+                (argv, Some(use_stdin))
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                return 1;
+            }
+        },
+    };
+
+    let binary_args: Vec<&str> = binary_args_owned.iter().map(String::as_str).collect();
 
     // TODO: fix binary_args validation
-    let gdb: GdbTriager = GdbTriager::new();
+    let debugger_kind = value_t!(args, "debugger", DebuggerKind).unwrap_or(DebuggerKind::gdb);
+
+    let gdb = match debugger_kind {
+        DebuggerKind::gdb => {
+            let mut gdb = GdbTriager::new();
+
+            if let Some(hostport) = args.value_of("gdb_remote") {
+                match parse_gdb_remote(hostport) {
+                    Ok((host, port)) => {
+                        gdb = gdb.with_remote_target(gdb_triage::GdbRemoteTarget {
+                            host,
+                            port,
+                            extended: args.is_present("gdb_remote_extended"),
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Invalid --gdb-remote {}: {}", hostport, e);
+                        return 1;
+                    }
+                }
+            }
+
+            if args.is_present("trace_syscalls") {
+                // Given with no value, `value_of` is `None`; "" means "all".
+                let filter = args.value_of("trace_syscalls").unwrap_or("");
+                let ring_buffer_size = value_t!(args, "trace_syscalls_ring_size", usize)
+                    .unwrap_or(gdb_triage::DEFAULT_SYSCALL_RING_BUFFER);
 
-    if !sanity_check(&gdb, &binary_args) {
+                gdb = gdb.with_syscall_trace(gdb_triage::SyscallTraceConfig {
+                    filter: parse_syscall_filter(filter),
+                    ring_buffer_size,
+                });
+            }
+
+            DebuggerBackend::Gdb(gdb)
+        }
+        DebuggerKind::lldb => {
+            if args.value_of("gdb_remote").is_some() {
+                log::error!("--gdb-remote is GDB-only; it has no effect with --debugger lldb");
+                return 1;
+            }
+
+            if args.is_present("trace_syscalls") {
+                log::error!("--trace-syscalls is GDB-only; it has no effect with --debugger lldb");
+                return 1;
+            }
+
+            DebuggerBackend::Lldb(LldbTriager::new())
+        }
+    };
+
+    let sanitizer = value_t!(args, "sanitizer", SanitizerSelector).unwrap_or(SanitizerSelector::both);
+
+    if !sanity_check(&gdb, &binary_args, sanitizer) {
         return 1;
     }
 
-    let input_stdin = args.is_present("stdin");
+    let input_stdin = derived_stdin.unwrap_or_else(|| args.is_present("stdin"));
     let has_atat = binary_args.iter().any(|s| *s == "@@");
 
     if input_stdin {
@@ -577,11 +991,9 @@ fn main_wrapper() -> i32 {
         None => log::info!("Reports output to terminal"),
     }
 
-    let input_paths: Vec<&str> = args.values_of("input").unwrap().collect();
-
     let mut processed_inputs = Vec::new();
 
-    for input in input_paths {
+    for input in &input_paths {
         let path = PathBuf::from(input);
         let ty = determine_input_type(&path);
 
@@ -601,6 +1013,38 @@ fn main_wrapper() -> i32 {
 
     let debug = args.is_present("debug");
     let child_output = args.is_present("child_output");
+    let output_format = value_t!(args, "ofmt", OutputFormat).unwrap_or(OutputFormat::text);
+
+    let major_hash_frames = value_t!(args, "major_hash_frames", usize)
+        .unwrap_or(stackhash::DEFAULT_MAJOR_HASH_FRAMES);
+
+    let cluster_threshold = value_t!(args, "cluster_threshold", f32)
+        .unwrap_or(cluster::DEFAULT_CLUSTER_THRESHOLD);
+
+    let frame_skip_regex = match args.value_of("frame_skip_regex") {
+        Some(pattern) => match Regex::new(pattern) {
+            Ok(re) => re,
+            Err(e) => {
+                log::error!("Invalid --frame-skip-regex: {}", e);
+                return 1;
+            }
+        },
+        None => stackhash::DEFAULT_FRAME_SKIP_REGEX.clone(),
+    };
+
+    let rule_set = match args.value_of("rules_file") {
+        Some(path) => match rules::load_rules(Path::new(path)) {
+            Ok(rules) => {
+                log::info!("Loaded {} rule(s) from {}", rules.len(), path);
+                rules
+            }
+            Err(e) => {
+                log::error!("Failed to load --rules-file {}: {}", path, e);
+                return 1;
+            }
+        },
+        None => Vec::new(),
+    };
 
     let child_output_lines = if let Ok(n) = value_t!(args, "child_output_lines", usize) {
         n
@@ -617,11 +1061,18 @@ fn main_wrapper() -> i32 {
         log::info!("Triage timeout set to {}ms", timeout_ms);
     }
 
+    let limits = process::ResourceLimits {
+        as_bytes: value_t!(args, "rlimit_as", u64).ok(),
+        cpu_secs: value_t!(args, "rlimit_cpu", u64).ok(),
+        core_bytes: value_t!(args, "rlimit_core", u64).ok(),
+        nofile: value_t!(args, "rlimit_nofile", u64).ok(),
+    };
+
     let mut max_recommended_threadcount = num_cpus::get();
 
     if !args.is_present("skip_profile") {
         let first_testcase_path = all_testcases[0].path.to_str().unwrap();
-        let profile_result = profile_target(&gdb, &binary_args, first_testcase_path, debug, input_stdin, timeout_ms);
+        let profile_result = profile_target(&gdb, &binary_args, first_testcase_path, debug, input_stdin, timeout_ms, limits);
 
         if let Ok(profile_result) = profile_result {
             if let std::io::Result::Err(e) = profile_result.process_result {
@@ -688,10 +1139,37 @@ fn main_wrapper() -> i32 {
     log::info!("Triaging {} testcases", all_testcases.len());
     log::info!("Using {} threads to triage", job_count);
 
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(job_count)
-        .build_global()
-        .unwrap();
+    let mut pool_builder = rayon::ThreadPoolBuilder::new().num_threads(job_count);
+
+    if args.is_present("bind_cpus") {
+        let busy_cores = platform::detect_busy_cores(0.5);
+        let free_cores: Vec<usize> = (0..num_cpus::get())
+            .filter(|c| !busy_cores.contains(c))
+            .collect();
+
+        if busy_cores.is_empty() {
+            log::info!("Pinning triage threads to CPU cores");
+        } else {
+            log::info!(
+                "Pinning triage threads to CPU cores, skipping {} busy core(s)",
+                busy_cores.len()
+            );
+        }
+
+        if free_cores.is_empty() {
+            log::warn!("No free CPU cores found - --bind-cpus has no effect");
+        } else {
+            pool_builder = pool_builder.start_handler(move |worker_index| {
+                let core_id = free_cores[worker_index % free_cores.len()];
+
+                if let Err(e) = platform::bind_current_thread_to_core(core_id) {
+                    log::warn!("Failed to pin worker {} to core {}: {}", worker_index, core_id, e);
+                }
+            });
+        }
+    }
+
+    pool_builder.build_global().unwrap();
 
     let pb = ProgressBar::new((&all_testcases).len() as u64);
 
@@ -704,180 +1182,422 @@ fn main_wrapper() -> i32 {
         pb.enable_steady_tick(200);
     }
 
-    let write_message: Box<dyn Fn(String, Option<&str>) + Sync> = if display_progress {
-        Box::new(|msg, tc| {
-            pb.set_message(&msg)
-        })
-    } else {
-        Box::new(|msg, tc| {
-            if let Some(tc_name) = tc {
-                log::info!("{}: {}", tc_name, msg)
-            } else {
-                log::info!("{}", msg)
-            }
-        })
-    };
-
-    write_message(format!("Processing initial {} test cases", job_count), None);
-
-    let state = Arc::new(Mutex::new(TriageState {
-        crashed: 0,
-        no_crash: 0,
-        errored: 0,
-        timedout: 0,
-        crash_signature: HashSet::new(),
-        unique_errors: HashMap::new(),
-    }));
-
-    all_testcases.par_iter().panic_fuse().for_each(|testcase| {
-        let path = testcase.path.to_str().unwrap();
-        let result = triage_test_case(&gdb, &binary_args, path, debug, input_stdin, timeout_ms);
-
-        let report = match &result {
-            TriageResult::Crash(triage) => Some(report::format_text_report(triage)),
-            _ => None,
+    // Workers only run the debugger, render the report, and evaluate rules --
+    // all independent, per-testcase work -- then hand a `TriageEvent` off to
+    // this channel. The aggregator thread below is the sole owner of
+    // `TriageState`, the progress bar, and report files, so there's no lock
+    // for workers to contend over on the hot path.
+    let (event_tx, event_rx) = unbounded::<TriageEvent>();
+
+    // The aggregator outlives this function's stack frame's borrows, so it
+    // needs its own owned copies of anything also still needed after
+    // `aggregator.join()` (the output dir) or by the worker closure below
+    // (the frame-skip regex) rather than moving the originals wholesale.
+    let frame_skip_regex_for_aggregator = frame_skip_regex.clone();
+    let output_dir_for_aggregator = output_dir.clone();
+
+    let aggregator = thread::spawn(move || {
+        let frame_skip_regex = frame_skip_regex_for_aggregator;
+        let output_dir = output_dir_for_aggregator;
+
+        let write_message: Box<dyn Fn(String, Option<&str>)> = if display_progress {
+            Box::new(|msg, _tc| pb.set_message(&msg))
+        } else {
+            Box::new(|msg, tc| {
+                if let Some(tc_name) = tc {
+                    log::info!("{}: {}", tc_name, msg)
+                } else {
+                    log::info!("{}", msg)
+                }
+            })
         };
 
-        // do very little with this lock held. do not reorder
-        let mut state = state.lock().unwrap();
+        write_message(format!("Processing initial {} test cases", job_count), None);
+
+        let mut state = TriageState {
+            crashed: 0,
+            no_crash: 0,
+            errored: 0,
+            timedout: 0,
+            crash_buckets: HashMap::new(),
+            unique_errors: HashMap::new(),
+            severity_counts: HashMap::new(),
+            crash_records: Vec::new(),
+            tag_counts: HashMap::new(),
+            suppressed: 0,
+        };
 
         // TODO: display child-output even without a crash to help debug triage errors
 
-        match result {
-            TriageResult::NoCrash(_child) => {
-                state.no_crash += 1;
+        for event in event_rx.iter() {
+            match event {
+                TriageEvent::NoCrash { path } => {
+                    state.no_crash += 1;
 
-                if !display_progress {
-                    write_message("No crash".into(), Some(path));
+                    if !display_progress {
+                        write_message("No crash".into(), Some(&path));
+                    }
                 }
-            }
-            TriageResult::Timedout => {
-                state.timedout += 1;
+                TriageEvent::Timedout { path } => {
+                    state.timedout += 1;
 
-                if !display_progress {
-                    write_message("Timed out".into(), Some(path));
+                    if !display_progress {
+                        write_message("Timed out".into(), Some(&path));
+                    }
                 }
-            }
-            TriageResult::Crash(triage) => {
-                let report = report.as_ref().unwrap();
+                TriageEvent::Crash { path, triage, mut report, verdicts } => {
+                    state.crashed += 1;
+
+                    let mut effective_severity = report.severity;
+                    let mut override_rule: Option<String> = None;
+                    let mut route: Option<String> = None;
+                    let mut rule_suppressed = false;
+
+                    for (rule_name, verdict) in &verdicts {
+                        match verdict {
+                            rules::RuleVerdict::Tag(label) => {
+                                *state.tag_counts.entry(label.clone()).or_insert(0) += 1;
+                            }
+                            rules::RuleVerdict::OverrideSeverity(sev) => {
+                                log::info!("Rule \"{}\" overrode severity to {}", rule_name, sev);
+                                effective_severity = *sev;
+                                override_rule = Some(rule_name.clone());
+                            }
+                            rules::RuleVerdict::Route(subdir) => route = Some(subdir.clone()),
+                            rules::RuleVerdict::Suppress => {
+                                state.suppressed += 1;
+                                rule_suppressed = true;
+                            }
+                        }
+                    }
 
-                state.crashed += 1;
+                    *state.severity_counts.entry(effective_severity).or_insert(0) += 1;
 
-                if !state.crash_signature.contains(&report.stackhash) {
-                    write_message(format!("{}", report.headline), Some(path));
+                    // Fold the rule's verdict into the rendered report body and
+                    // output filename (both already computed with the raw
+                    // classifier's severity in the worker thread), not just the
+                    // `effective_severity` counter above -- otherwise
+                    // `OverrideSeverity` never shows up in anything a user
+                    // actually opens.
+                    let severity_override = override_rule
+                        .as_deref()
+                        .map(|rule_name| (effective_severity, rule_name));
 
-                    state.crash_signature.insert(report.stackhash.to_string());
+                    if let Some((severity, rule_name)) = severity_override {
+                        report.apply_severity_override(severity, rule_name);
+                    }
 
-                    let mut text_report = format!(
-                        "Summary: {}\nCommand line: {}\nTestcase: {}\nStack hash: {}\n\n",
-                        report.headline, binary_cmdline, path, report.stackhash
-                    );
+                    if rule_suppressed {
+                        if !display_progress {
+                            write_message(format!("{} (suppressed by rule)", report.headline), Some(&path));
+                        }
 
-                    text_report += &format!("Register info:\n{}\n", report.register_info);
-                    text_report += &format!("Crash context:\n{}\n", report.crash_context);
-                    text_report += &format!("Crashing thread backtrace:\n{}\n", report.backtrace);
+                        if display_progress {
+                            pb.inc(1);
+                        }
 
-                    if !report.asan_body.is_empty() {
-                        text_report += &format!("ASAN Report:\n{}\n", report.asan_body);
+                        continue;
                     }
 
-                    let mut format_output = |name: &str, output: &str| {
-                        if output.is_empty() {
-                            text_report.push_str(&format!("\nChild {} (no output):\n", name));
-                        } else if child_output_lines == 0 {
-                            text_report
-                                .push_str(&format!("\nChild {} (everything):\n{}\n", name, output));
-                        } else {
-                            let lines = util::tail_string(output, child_output_lines);
-                            text_report.push_str(&format!(
-                                "\nChild {} (last {} lines):\n",
-                                name, child_output_lines
-                            ));
-                            for (i, line) in lines.iter().enumerate() {
-                                if line.is_empty() && i + 1 == lines.len() {
-                                    break;
-                                }
-                                text_report.push_str(&format!("{}\n", line));
+                    let bucket = state
+                        .crash_buckets
+                        .entry(report.major_hash.clone())
+                        .or_insert_with(HashSet::new);
+                    let is_new_variant = bucket.insert(report.minor_hash.clone());
+
+                    if is_new_variant {
+                        write_message(report.headline.clone(), Some(&path));
+
+                        let mut text_report = match output_format {
+                            OutputFormat::markdown => {
+                                report::format_markdown_report(
+                                    &triage,
+                                    &binary_cmdline,
+                                    &path,
+                                    major_hash_frames,
+                                    &frame_skip_regex,
+                                    &limits,
+                                    severity_override,
+                                )
                             }
-                        }
-                    };
+                            // One JSON object (or, to stdout, one JSONL line) per crash,
+                            // carrying the full child STDOUT/STDERR already -- skip the
+                            // free-form text renderer and the child-output appending below.
+                            OutputFormat::json => {
+                                let json_report = report::format_json_report(
+                                    &triage,
+                                    &binary_cmdline,
+                                    &path,
+                                    major_hash_frames,
+                                    &frame_skip_regex,
+                                    &limits,
+                                    severity_override,
+                                );
+
+                                serde_json::to_string(&json_report).unwrap_or_else(|e| {
+                                    format!("{{\"error\": \"failed to serialize report: {}\"}}", e)
+                                })
+                            }
+                            OutputFormat::text => {
+                                let mut text_report = format!(
+                                    "Summary: {}\nCommand line: {}\nTestcase: {}\nMajor hash: {}\nMinor hash: {}\nSeverity: {} ({})\n\n",
+                                    report.headline, binary_cmdline, path, report.major_hash, report.minor_hash,
+                                    report.severity, report.severity_rationale
+                                );
+
+                                text_report += &format!("Register info:\n{}\n", report.register_info);
+                                text_report += &format!("Crash context:\n{}\n", report.crash_context);
+                                text_report += &format!("Crashing thread backtrace:\n{}\n", report.backtrace);
+
+                                if !report.asan_body.is_empty() {
+                                    text_report += &format!("ASAN Report:\n{}\n", report.asan_body);
+                                }
 
-                    if child_output {
-                        // Dont include the ASAN report duplicated in the child's STDERR
-                        let stderr = if report.asan_body.is_empty() {
-                            triage.child.stderr
-                        } else {
-                            triage
-                                .child
-                                .stderr
-                                .replace(&report.asan_body, "<ASAN Report>")
+                                if !report.ubsan_body.is_empty() {
+                                    text_report += &format!("UBSAN Report:\n{}\n", report.ubsan_body);
+                                }
+
+                                text_report
+                            }
                         };
 
-                        format_output("STDOUT", &triage.child.stdout);
-                        format_output("STDERR", &stderr);
-                    }
+                        let mut format_output = |name: &str, output: &str| {
+                            if output.is_empty() {
+                                text_report.push_str(&format!("\nChild {} (no output):\n", name));
+                            } else if child_output_lines == 0 {
+                                text_report
+                                    .push_str(&format!("\nChild {} (everything):\n{}\n", name, output));
+                            } else {
+                                let lines = util::tail_string(output, child_output_lines);
+                                text_report.push_str(&format!(
+                                    "\nChild {} (last {} lines):\n",
+                                    name, child_output_lines
+                                ));
+                                for (i, line) in lines.iter().enumerate() {
+                                    if line.is_empty() && i + 1 == lines.len() {
+                                        break;
+                                    }
+                                    text_report.push_str(&format!("{}\n", line));
+                                }
+                            }
+                        };
 
-                    if output_dir.is_none() {
-                        write_message(format!(
-                            "--- REPORT BEGIN ---\n{}\n--- REPORT END ---",
-                            text_report,
-                        ), None);
-                    } else {
-                        let output_dir = output_dir.as_ref().unwrap();
-                        let report_filename = format!(
-                            "afltriage_{}_{}.txt",
-                            util::sanitize(&report.terse_headline),
-                            &report.stackhash[..8]
+                        let trace = stackhash::normalized_trace(
+                            &triage.response.result.as_ref().unwrap().primary_thread.backtrace,
                         );
 
-                        if let Err(e) =
-                            std::fs::write(output_dir.join(report_filename), text_report)
-                        {
-                            // TODO: notify / exit early
-                            let failed_to_write = format!("Failed to write report: {}", e);
-                            write_message(failed_to_write, Some(path));
+                        if child_output && output_format != OutputFormat::json {
+                            // Dont include the ASAN report duplicated in the child's STDERR
+                            let stderr = if report.asan_body.is_empty() {
+                                triage.child.stderr
+                            } else {
+                                triage
+                                    .child
+                                    .stderr
+                                    .replace(&report.asan_body, "<ASAN Report>")
+                            };
+
+                            format_output("STDOUT", &triage.child.stdout);
+                            format_output("STDERR", &stderr);
                         }
+
+                        if output_dir.is_none() && output_format == OutputFormat::json {
+                            // Bypass write_message/the logger entirely: log::info!'s
+                            // formatter prepends "[+] " to every line, which would
+                            // corrupt the JSON and defeat the whole point of
+                            // `--output-format json` (piping into dashboards/CI gates
+                            // instead of grepping text).
+                            println!("{}", text_report);
+                        } else if output_dir.is_none() {
+                            write_message(format!(
+                                "--- REPORT BEGIN ---\n{}\n--- REPORT END ---",
+                                text_report,
+                            ), None);
+                        } else {
+                            let extension = match output_format {
+                                OutputFormat::text => "txt",
+                                OutputFormat::markdown => "md",
+                                OutputFormat::json => "json",
+                            };
+                            let report_filename = format!(
+                                "afltriage_{}_{}.{}",
+                                util::sanitize(&report.terse_headline),
+                                &report.major_hash[..8],
+                                extension
+                            );
+
+                            // Reports are written after clustering (in a final, sequential
+                            // pass) so they can land in their cluster_000N/ subdirectory.
+                            state.crash_records.push(CrashRecord {
+                                trace,
+                                filename: report_filename,
+                                text_report,
+                                route,
+                            });
+                        }
+                    } else {
+                        write_message(report.headline.clone(), Some(&path));
                     }
-                } else {
-                    write_message(format!("{}", report.headline), Some(path));
                 }
-            }
-            TriageResult::Error(gdb_error) => {
-                state.errored += 1;
+                TriageEvent::Error { path, error } => {
+                    state.errored += 1;
 
-                write_message(format!("ERROR: {}", gdb_error.error), Some(path));
+                    write_message(format!("ERROR: {}", error.error), Some(&path));
 
-                if let Some(x) = state.unique_errors.get_mut(&gdb_error) {
-                    *x += 1;
-                } else {
-                    state.unique_errors.insert(gdb_error, 1);
+                    if let Some(x) = state.unique_errors.get_mut(&error) {
+                        *x += 1;
+                    } else {
+                        state.unique_errors.insert(error, 1);
+                    }
                 }
+            };
+
+            if display_progress {
+                pb.inc(1);
             }
-        };
+        }
 
         if display_progress {
-            pb.inc(1);
+            pb.finish();
+        } else {
+            pb.finish_and_clear();
         }
+
+        state
     });
 
-    if display_progress {
-        pb.finish();
-    } else {
-        pb.finish_and_clear();
-    }
+    all_testcases.par_iter().panic_fuse().for_each(|testcase| {
+        let path = testcase.path.to_str().unwrap().to_string();
+        let result = triage_test_case(&gdb, &binary_args, &path, debug, input_stdin, timeout_ms, limits);
+
+        let event = match result {
+            TriageResult::NoCrash(_child) => TriageEvent::NoCrash { path },
+            TriageResult::Timedout => TriageEvent::Timedout { path },
+            TriageResult::Error(error) => TriageEvent::Error { path, error },
+            TriageResult::Crash(triage) => {
+                let report =
+                    report::format_text_report(&triage, major_hash_frames, &frame_skip_regex, &limits, None);
 
-    let state = state.lock().unwrap();
+                let top_frame_function = triage
+                    .response
+                    .result
+                    .as_ref()
+                    .and_then(|ctx| ctx.primary_thread.backtrace.get(0))
+                    .and_then(|f| f.symbol.as_ref())
+                    .and_then(|s| s.function_name.clone())
+                    .unwrap_or_default();
+
+                let verdicts = rules::evaluate(&rule_set, &report, &top_frame_function);
+
+                TriageEvent::Crash { path, triage, report, verdicts }
+            }
+        };
+
+        if event_tx.send(event).is_err() {
+            log::error!("Aggregator thread exited early; triage result lost");
+        }
+    });
+
+    // Once every worker has sent its last event, dropping the original sender
+    // closes the channel so the aggregator's `event_rx.iter()` terminates.
+    drop(event_tx);
+
+    let mut state = aggregator.join().expect("aggregator thread panicked");
     let total = all_testcases.len();
 
+    let unique_crashes = state.crash_buckets.len();
+    let crash_variants: usize = state.crash_buckets.values().map(HashSet::len).sum();
+
+    // Triage runs in parallel, so the order crash_records were discovered in
+    // isn't stable across runs; sort first so clustering (and therefore
+    // cluster_000N/ numbering) is deterministic.
+    state.crash_records.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+    let mut cluster_count = 0;
+
+    if let Some(output_dir) = &output_dir {
+        // Rule-routed reports bypass fuzzy clustering entirely and land
+        // directly in their named subdirectory.
+        for record in state.crash_records.iter().filter(|r| r.route.is_some()) {
+            let routed_dir = output_dir.join(record.route.as_ref().unwrap());
+
+            if let Err(e) = std::fs::create_dir_all(&routed_dir) {
+                log::error!("Failed to create routed directory: {}", e);
+                continue;
+            }
+
+            if let Err(e) = std::fs::write(routed_dir.join(&record.filename), &record.text_report) {
+                log::error!("Failed to write report {}: {}", record.filename, e);
+            }
+        }
+
+        let unrouted: Vec<&CrashRecord> =
+            state.crash_records.iter().filter(|r| r.route.is_none()).collect();
+
+        if !unrouted.is_empty() {
+            let traces: Vec<Vec<String>> = unrouted.iter().map(|r| r.trace.clone()).collect();
+            let assignment = cluster::cluster(&traces, cluster_threshold);
+
+            cluster_count = assignment.iter().copied().max().map(|m| m + 1).unwrap_or(0);
+
+            for (record, cluster_id) in unrouted.iter().zip(assignment.iter()) {
+                let cluster_dir = output_dir.join(format!("cluster_{:04}", cluster_id + 1));
+
+                if let Err(e) = std::fs::create_dir_all(&cluster_dir) {
+                    log::error!("Failed to create cluster directory: {}", e);
+                    continue;
+                }
+
+                if let Err(e) = std::fs::write(cluster_dir.join(&record.filename), &record.text_report) {
+                    log::error!("Failed to write report {}: {}", record.filename, e);
+                }
+            }
+        }
+    }
+
     log::info!(
-        "Triage stats [Crashes: {} (unique {}), No crash: {}, Timeout: {}, Errored: {}]",
+        "Triage stats [Crashes: {} (unique {} crashes, {} variants, {} clusters), No crash: {}, Timeout: {}, Errored: {}]",
         state.crashed,
-        state.crash_signature.len(),
+        unique_crashes,
+        crash_variants,
+        cluster_count,
         state.no_crash,
         state.timedout,
         state.errored
     );
 
+    if state.crashed > 0 {
+        let severity_summary = [
+            Severity::EXPLOITABLE,
+            Severity::PROBABLY_EXPLOITABLE,
+            Severity::PROBABLY_NOT_EXPLOITABLE,
+            Severity::UNKNOWN,
+        ]
+        .iter()
+        .map(|sev| format!("{}: {}", sev, state.severity_counts.get(sev).unwrap_or(&0)))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+        log::info!("Severity breakdown [{}]", severity_summary);
+    }
+
+    if !state.tag_counts.is_empty() {
+        let mut tags: Vec<(&String, &usize)> = state.tag_counts.iter().collect();
+        tags.sort_by(|a, b| a.0.cmp(b.0));
+
+        let tag_summary = tags
+            .iter()
+            .map(|(tag, count)| format!("{}: {}", tag, count))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        log::info!("Rule tags [{}]", tag_summary);
+    }
+
+    if state.suppressed > 0 {
+        log::info!("{} crash(es) suppressed by rules", state.suppressed);
+    }
+
     if state.errored == total {
         log::error!("Something seems to be wrong during triage as all testcases errored.");
     }
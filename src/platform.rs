@@ -0,0 +1,104 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+//
+// Small OS-specific helpers that don't belong in any other module.
+use std::collections::HashSet;
+
+/// Pin the calling thread to a single CPU core, à la AFL++'s `bind_cpu`.
+/// Only implemented on Linux/FreeBSD where `sched_setaffinity` is available;
+/// a no-op (with a warning) everywhere else.
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+pub fn bind_current_thread_to_core(core_id: usize) -> std::io::Result<()> {
+    use nix::sched::{sched_setaffinity, CpuSet};
+    use nix::unistd::Pid;
+
+    let mut cpu_set = CpuSet::new();
+    cpu_set
+        .set(core_id)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+    sched_setaffinity(Pid::from_raw(0), &cpu_set)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+pub fn bind_current_thread_to_core(_core_id: usize) -> std::io::Result<()> {
+    log::warn!("--bind-cpus is not supported on this platform");
+    Ok(())
+}
+
+/// Best-effort detection of CPU cores that are already under heavy load, so
+/// `--bind-cpus` can skip them instead of adding to the contention. Samples
+/// `/proc/stat` twice over a short window and returns the indices of cores
+/// whose non-idle time exceeds `busy_threshold` (0.0-1.0) of the window.
+/// Returns an empty set (i.e. "nothing looks busy") on any platform or
+/// parsing error, since this is only ever used to narrow core selection.
+#[cfg(target_os = "linux")]
+pub fn detect_busy_cores(busy_threshold: f32) -> HashSet<usize> {
+    fn read_per_core_totals() -> Option<Vec<(u64, u64)>> {
+        let stat = std::fs::read_to_string("/proc/stat").ok()?;
+        let mut cores = Vec::new();
+
+        for line in stat.lines() {
+            if !line.starts_with("cpu") || line.starts_with("cpu ") {
+                continue;
+            }
+
+            let fields: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|f| f.parse().ok())
+                .collect();
+
+            if fields.len() < 4 {
+                continue;
+            }
+
+            // user + nice + system + irq + softirq + steal, vs idle + iowait
+            let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+            let total: u64 = fields.iter().sum();
+            cores.push((idle, total));
+        }
+
+        Some(cores)
+    }
+
+    let before = match read_per_core_totals() {
+        Some(v) => v,
+        None => return HashSet::new(),
+    };
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+
+    let after = match read_per_core_totals() {
+        Some(v) => v,
+        None => return HashSet::new(),
+    };
+
+    let mut busy = HashSet::new();
+
+    for (i, ((idle_before, total_before), (idle_after, total_after))) in
+        before.iter().zip(after.iter()).enumerate()
+    {
+        let total_delta = total_after.saturating_sub(*total_before);
+        let idle_delta = idle_after.saturating_sub(*idle_before);
+
+        if total_delta == 0 {
+            continue;
+        }
+
+        let busy_fraction = 1.0 - (idle_delta as f32 / total_delta as f32);
+
+        if busy_fraction >= busy_threshold {
+            busy.insert(i);
+        }
+    }
+
+    busy
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn detect_busy_cores(_busy_threshold: f32) -> HashSet<usize> {
+    HashSet::new()
+}
@@ -0,0 +1,208 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct ChildResult {
+    pub stdout: String,
+    pub stderr: String,
+    pub status: ExitStatus,
+}
+
+/// Resource limits applied to the debugged child before it execs, as done in
+/// the coreutils test harness. `None` leaves a limit at its inherited value.
+/// `RLIMIT_CORE` is normally forced to 0 so triage never litters core files.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ResourceLimits {
+    pub as_bytes: Option<u64>,
+    pub cpu_secs: Option<u64>,
+    pub core_bytes: Option<u64>,
+    pub nofile: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn core_disabled() -> ResourceLimits {
+        ResourceLimits {
+            core_bytes: Some(0),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(unix)]
+fn apply_rlimits(cmd: &mut Command, limits: ResourceLimits) {
+    use nix::sys::resource::{setrlimit, Resource};
+    use std::os::unix::process::CommandExt;
+
+    unsafe {
+        cmd.pre_exec(move || {
+            // Best effort: a limit that fails to apply is logged by the
+            // caller via the child's eventual exit status, not here (this
+            // closure runs in the forked child, before exec).
+            if let Some(v) = limits.as_bytes {
+                let _ = setrlimit(Resource::RLIMIT_AS, v, v);
+            }
+            if let Some(v) = limits.cpu_secs {
+                let _ = setrlimit(Resource::RLIMIT_CPU, v, v);
+            }
+            if let Some(v) = limits.core_bytes {
+                let _ = setrlimit(Resource::RLIMIT_CORE, v, v);
+            }
+            if let Some(v) = limits.nofile {
+                let _ = setrlimit(Resource::RLIMIT_NOFILE, v, v);
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_rlimits(_cmd: &mut Command, _limits: ResourceLimits) {}
+
+/// Shared by the GDB and LLDB backends: `setrlimit` persists across `exec`,
+/// so applying `--rlimit-*` to the `Command` that spawns the debugger
+/// itself (as a previous version of this code did) can OOM-kill or
+/// CPU-starve the debugger before it ever gets to run the target. Both
+/// backends instead make the debugger launch the inferior through this tiny
+/// wrapper: the `ulimit`s here apply only to this shell and are inherited
+/// across its own `exec`, landing on the debuggee and nowhere else.
+pub(crate) const RLIMIT_WRAPPER_SCRIPT: &str = "#!/bin/sh\n\
+[ -n \"$AFLTRIAGE_RLIMIT_AS\" ] && ulimit -v \"$((AFLTRIAGE_RLIMIT_AS / 1024))\"\n\
+[ -n \"$AFLTRIAGE_RLIMIT_CPU\" ] && ulimit -t \"$AFLTRIAGE_RLIMIT_CPU\"\n\
+[ -n \"$AFLTRIAGE_RLIMIT_CORE\" ] && ulimit -c \"$((AFLTRIAGE_RLIMIT_CORE / 1024))\"\n\
+[ -n \"$AFLTRIAGE_RLIMIT_NOFILE\" ] && ulimit -n \"$AFLTRIAGE_RLIMIT_NOFILE\"\n\
+exec \"$@\"\n";
+
+/// Materialize `RLIMIT_WRAPPER_SCRIPT` as an executable temp file the
+/// debugger can be pointed at to launch the debuggee through.
+pub(crate) fn write_rlimit_wrapper() -> tempfile::NamedTempFile {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tf = tempfile::Builder::new().suffix(".sh").tempfile().unwrap();
+    std::fs::write(tf.path(), RLIMIT_WRAPPER_SCRIPT).unwrap();
+    std::fs::set_permissions(tf.path(), std::fs::Permissions::from_mode(0o700)).unwrap();
+
+    tf
+}
+
+/// The `AFLTRIAGE_RLIMIT_*` env vars `RLIMIT_WRAPPER_SCRIPT` reads, as
+/// `(name, value)` pairs for whichever limits are actually set -- shared so
+/// the GDB and LLDB backends don't each re-derive the env var names.
+pub(crate) fn rlimit_env_vars(limits: ResourceLimits) -> Vec<(&'static str, u64)> {
+    let mut vars = Vec::new();
+
+    if let Some(v) = limits.as_bytes {
+        vars.push(("AFLTRIAGE_RLIMIT_AS", v));
+    }
+    if let Some(v) = limits.cpu_secs {
+        vars.push(("AFLTRIAGE_RLIMIT_CPU", v));
+    }
+    if let Some(v) = limits.core_bytes {
+        vars.push(("AFLTRIAGE_RLIMIT_CORE", v));
+    }
+    if let Some(v) = limits.nofile {
+        vars.push(("AFLTRIAGE_RLIMIT_NOFILE", v));
+    }
+
+    vars
+}
+
+pub fn execute_capture_output(program: &str, args: &[&str]) -> std::io::Result<ChildResult> {
+    let output = Command::new(program).args(args).output()?;
+
+    Ok(ChildResult {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        status: output.status,
+    })
+}
+
+/// Spawn `program` with piped stdin/stdout/stderr and, if given, its rlimits
+/// applied, handing back the live `Child` instead of waiting for it to exit.
+/// For callers that drive a long-lived child through a request/response
+/// protocol over its pipes (e.g. `gdb_triage::GdbSession`) rather than
+/// running it to completion like `execute_capture_output_timeout` does.
+pub fn spawn_piped(
+    program: &str,
+    args: &[String],
+    limits: Option<ResourceLimits>,
+) -> std::io::Result<std::process::Child> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(limits) = limits {
+        apply_rlimits(&mut cmd, limits);
+    }
+
+    cmd.spawn()
+}
+
+pub fn execute_capture_output_timeout(
+    program: &str,
+    args: &[String],
+    timeout_ms: u64,
+    input: Option<Vec<u8>>,
+    limits: Option<ResourceLimits>,
+) -> std::io::Result<ChildResult> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(limits) = limits {
+        apply_rlimits(&mut cmd, limits);
+    }
+
+    let mut child = cmd.spawn()?;
+
+    if let Some(bytes) = input {
+        if let Some(stdin) = child.stdin.as_mut() {
+            // The child may exit/close stdin before we finish writing (e.g.
+            // it doesn't read all of its input); that's not our error.
+            let _ = stdin.write_all(&bytes);
+        }
+    }
+    child.stdin.take();
+
+    let start = Instant::now();
+    let timeout = Duration::from_millis(timeout_ms);
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let mut stdout = String::new();
+            let mut stderr = String::new();
+
+            if let Some(mut out) = child.stdout.take() {
+                let _ = out.read_to_string(&mut stdout);
+            }
+            if let Some(mut err) = child.stderr.take() {
+                let _ = err.read_to_string(&mut stderr);
+            }
+
+            return Ok(ChildResult {
+                stdout,
+                stderr,
+                status,
+            });
+        }
+
+        if timeout_ms != 0 && start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("Timed out after {}ms", timeout_ms),
+            ));
+        }
+
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+use regex::Regex;
+use serde::Serialize;
+
+use crate::gdb_triage::GdbTriageResult;
+use crate::process::ResourceLimits;
+use crate::sanitizer::{self, UbsanDiagnostic};
+use crate::severity::{self, Severity};
+use crate::stackhash;
+
+/// A clear note to attach to the report when the observed signal is likely
+/// the child being killed for exceeding a configured rlimit, rather than a
+/// genuine crash in the target.
+fn rlimit_kill_note(signal: &str, limits: &ResourceLimits) -> Option<String> {
+    match signal {
+        "SIGXCPU" if limits.cpu_secs.is_some() => Some(format!(
+            "NOTE: target received SIGXCPU, consistent with exceeding --rlimit-cpu ({}s)",
+            limits.cpu_secs.unwrap()
+        )),
+        "SIGSEGV" | "SIGABRT" | "SIGKILL" if limits.as_bytes.is_some() => Some(format!(
+            "NOTE: target may have been killed for exceeding --rlimit-as ({} bytes) rather than a genuine crash",
+            limits.as_bytes.unwrap()
+        )),
+        _ => None,
+    }
+}
+
+/// A fully rendered triage report for a single crashing testcase. The text
+/// fields are already formatted for display; `format_text_report` is the
+/// single place that turns the raw GDB triage JSON into these strings so
+/// every `OutputFormat` renders from the same source of truth.
+pub struct TriageReport {
+    pub headline: String,
+    pub terse_headline: String,
+    /// Display stack hash (the minor hash). Full dedup uses `major_hash`.
+    pub stackhash: String,
+    pub major_hash: String,
+    pub minor_hash: String,
+    pub register_info: String,
+    pub crash_context: String,
+    pub backtrace: String,
+    pub asan_body: String,
+    pub ubsan_body: String,
+    pub severity: Severity,
+    pub severity_rationale: String,
+    /// The signal and top frame `headline`/`terse_headline` were folded
+    /// from, kept around so `apply_severity_override` can rebuild them
+    /// without re-running the whole classifier.
+    signal: String,
+    top_frame: String,
+}
+
+impl TriageReport {
+    /// Rule evaluation (`rules::evaluate`) runs after this report is built
+    /// and only hands back a verdict, not a new report; a rule's
+    /// `OverrideSeverity` verdict reaches the rendered report/filename by
+    /// patching it in here instead of re-running `format_text_report`.
+    pub fn apply_severity_override(&mut self, severity: Severity, rule_name: &str) {
+        if severity == self.severity {
+            return;
+        }
+
+        self.severity_rationale = format!(
+            "{} (overridden from {} to {} by rule \"{}\")",
+            self.severity_rationale, self.severity, severity, rule_name
+        );
+        self.severity = severity;
+        self.headline = format!("[{}] {} in {}", self.severity, self.signal, self.top_frame);
+        self.terse_headline = format!("{}_{}_{}", self.severity, self.signal, self.top_frame);
+    }
+}
+
+pub fn format_text_report(
+    triage: &GdbTriageResult,
+    major_hash_frames: usize,
+    frame_skip_regex: &Regex,
+    limits: &ResourceLimits,
+    severity_override: Option<(Severity, &str)>,
+) -> TriageReport {
+    let ctx = triage
+        .response
+        .result
+        .as_ref()
+        .expect("format_text_report called without a crash context");
+
+    let stop = &ctx.stop_info;
+    let primary = &ctx.primary_thread;
+
+    let mut crash_context = match stop.faulting_address {
+        Some(addr) => format!("Signal: {} (si_code={})\nFaulting address: {:#018x}", stop.signal, stop.signal_code, addr),
+        None => format!("Signal: {} (si_code={})", stop.signal, stop.signal_code),
+    };
+
+    if let Some(note) = rlimit_kill_note(&stop.signal, limits) {
+        crash_context += &format!("\n{}", note);
+    }
+
+    let register_info = match &primary.registers {
+        Some(regs) => regs
+            .iter()
+            .map(|r| format!("{:<8}{}", r.name, r.pretty_value))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        None => "<no registers>".to_string(),
+    };
+
+    let backtrace = primary
+        .backtrace
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| match &frame.symbol {
+            Some(sym) => format!("#{:<2} {:#018x} {}", i, frame.address, sym.format_function_prototype()),
+            None => format!("#{:<2} {:#018x} {} ({})", i, frame.address, frame.module, frame.relative_address),
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let top_frame = primary
+        .backtrace
+        .get(0)
+        .and_then(|f| f.symbol.as_ref())
+        .map(|s| s.format_short())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let mut ubsan_diagnostics: Vec<UbsanDiagnostic> =
+        sanitizer::extract_ubsan_diagnostics(&triage.child.stderr);
+    ubsan_diagnostics.extend(sanitizer::extract_ubsan_diagnostics(&triage.child.stdout));
+
+    let ubsan_signature = ubsan_diagnostics.first().map(|d| d.kind.as_str());
+
+    // ASAN writes its report to stderr by default, but fall back to stdout
+    // in case the target redirected it (mirrors the UBSAN extraction above).
+    let asan_body = sanitizer::extract_asan_body(&triage.child.stderr)
+        .or_else(|| sanitizer::extract_asan_body(&triage.child.stdout))
+        .unwrap_or_default();
+
+    let minor_hash = stackhash::minor_hash(&primary.backtrace);
+    let major_hash =
+        stackhash::major_hash(&primary.backtrace, major_hash_frames, frame_skip_regex, ubsan_signature);
+
+    let severity_result = severity::classify(ctx, &asan_body, &ubsan_diagnostics);
+
+    // Severity is folded into both the headline (for humans) and the terse
+    // headline (for output filenames / write_message), so a crash's
+    // exploitability is visible without opening the report.
+    let headline = format!("[{}] {} in {}", severity_result.severity, stop.signal, top_frame);
+    let terse_headline = format!("{}_{}_{}", severity_result.severity, stop.signal, top_frame);
+
+    let mut report = TriageReport {
+        headline,
+        terse_headline,
+        stackhash: minor_hash.clone(),
+        major_hash,
+        minor_hash,
+        register_info,
+        crash_context,
+        backtrace,
+        asan_body,
+        ubsan_body: sanitizer::format_report_section(&ubsan_diagnostics),
+        severity: severity_result.severity,
+        severity_rationale: severity_result.rationale,
+        signal: stop.signal.clone(),
+        top_frame,
+    };
+
+    if let Some((severity, rule_name)) = severity_override {
+        report.apply_severity_override(severity, rule_name);
+    }
+
+    report
+}
+
+pub fn format_markdown_report(
+    triage: &GdbTriageResult,
+    binary_cmdline: &str,
+    testcase: &str,
+    major_hash_frames: usize,
+    frame_skip_regex: &Regex,
+    limits: &ResourceLimits,
+    severity_override: Option<(Severity, &str)>,
+) -> String {
+    let report = format_text_report(triage, major_hash_frames, frame_skip_regex, limits, severity_override);
+
+    let mut out = format!(
+        "## {}\n\n- **Command line**: `{}`\n- **Testcase**: `{}`\n- **Major hash**: `{}`\n- **Minor hash**: `{}`\n- **Severity**: `{}` ({})\n\n### Registers\n```\n{}\n```\n\n### Crash context\n```\n{}\n```\n\n### Backtrace\n```\n{}\n```\n",
+        report.headline,
+        binary_cmdline,
+        testcase,
+        report.major_hash,
+        report.minor_hash,
+        report.severity,
+        report.severity_rationale,
+        report.register_info,
+        report.crash_context,
+        report.backtrace,
+    );
+
+    if !report.ubsan_body.is_empty() {
+        out += &format!("\n### UBSAN diagnostics\n```\n{}\n```\n", report.ubsan_body);
+    }
+
+    out
+}
+
+/// A single queryable backtrace frame, as opposed to the pre-rendered line
+/// used in `TriageReport::backtrace`.
+#[derive(Serialize)]
+pub struct JsonFrame {
+    pub address: u64,
+    pub symbol: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<i64>,
+    pub module: String,
+}
+
+/// A structured, serde-serializable triage report, for `--output-format
+/// json` consumers (dashboards, CI gates; one object per report file, or one
+/// JSONL line per crash when printing to stdout) that would otherwise have
+/// to scrape the free-form text report.
+#[derive(Serialize)]
+pub struct JsonTriageReport {
+    pub headline: String,
+    pub terse_headline: String,
+    pub stackhash: String,
+    pub major_hash: String,
+    pub minor_hash: String,
+    pub severity: Severity,
+    pub severity_rationale: String,
+    pub register_info: String,
+    pub crash_context: String,
+    pub backtrace: Vec<JsonFrame>,
+    pub asan_body: String,
+    pub ubsan_body: String,
+    pub command_line: String,
+    pub testcase: String,
+    pub child_stdout: String,
+    pub child_stderr: String,
+}
+
+pub fn format_json_report(
+    triage: &GdbTriageResult,
+    binary_cmdline: &str,
+    testcase: &str,
+    major_hash_frames: usize,
+    frame_skip_regex: &Regex,
+    limits: &ResourceLimits,
+    severity_override: Option<(Severity, &str)>,
+) -> JsonTriageReport {
+    let report = format_text_report(triage, major_hash_frames, frame_skip_regex, limits, severity_override);
+
+    let backtrace = triage
+        .response
+        .result
+        .as_ref()
+        .expect("format_json_report called without a crash context")
+        .primary_thread
+        .backtrace
+        .iter()
+        .map(|frame| JsonFrame {
+            address: frame.address,
+            symbol: frame.symbol.as_ref().map(|s| s.format_short()),
+            file: frame.symbol.as_ref().and_then(|s| s.file.clone()),
+            line: frame.symbol.as_ref().and_then(|s| s.line),
+            module: frame.module.clone(),
+        })
+        .collect();
+
+    JsonTriageReport {
+        headline: report.headline,
+        terse_headline: report.terse_headline,
+        stackhash: report.stackhash,
+        major_hash: report.major_hash,
+        minor_hash: report.minor_hash,
+        severity: report.severity,
+        severity_rationale: report.severity_rationale,
+        register_info: report.register_info,
+        crash_context: report.crash_context,
+        backtrace,
+        asan_body: report.asan_body,
+        ubsan_body: report.ubsan_body,
+        command_line: binary_cmdline.to_string(),
+        testcase: testcase.to_string(),
+        child_stdout: triage.child.stdout.clone(),
+        child_stderr: triage.child.stderr.clone(),
+    }
+}
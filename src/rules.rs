@@ -0,0 +1,178 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+//
+// Declarative triage rules, modeled on Fuchsia's Detect/triage config: each
+// rule's `match` section is a set of regexes over diagnostic data (the
+// rendered headline, the full backtrace, the ASAN/UBSAN body, or the
+// crashing function name) that are ANDed together, and its `action` fires
+// once every populated criterion matches. This lets users allowlist known
+// bugs and bucket campaigns without editing source.
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::report::TriageReport;
+use crate::severity::Severity;
+
+#[derive(Debug, Default, Deserialize)]
+struct RuleMatch {
+    headline: Option<String>,
+    backtrace: Option<String>,
+    asan_body: Option<String>,
+    ubsan_body: Option<String>,
+    crashing_function: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RuleAction {
+    Tag { label: String },
+    OverrideSeverity { severity: Severity },
+    Route { subdir: String },
+    Suppress,
+}
+
+#[derive(Debug, Deserialize)]
+struct Rule {
+    name: String,
+    #[serde(default, rename = "match")]
+    criteria: RuleMatch,
+    action: RuleAction,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    rule: Vec<Rule>,
+}
+
+/// What a matching rule's action resolves to, resolved out of `RuleAction`
+/// so the caller doesn't need to know about the rule's raw (string) config
+/// representation.
+pub enum RuleVerdict {
+    Tag(String),
+    OverrideSeverity(Severity),
+    Route(String),
+    Suppress,
+}
+
+/// A `Rule` with its `match` patterns pre-compiled into `Regex`es, since
+/// `regex::Regex` itself isn't `Deserialize`.
+pub struct CompiledRule {
+    name: String,
+    headline: Option<Regex>,
+    backtrace: Option<Regex>,
+    asan_body: Option<Regex>,
+    ubsan_body: Option<Regex>,
+    crashing_function: Option<Regex>,
+    action: RuleAction,
+}
+
+fn compile_pattern(name: &str, field: &str, pattern: &Option<String>) -> Result<Option<Regex>, String> {
+    match pattern {
+        Some(pattern) => Regex::new(pattern)
+            .map(Some)
+            .map_err(|e| format!("rule \"{}\": invalid {} regex: {}", name, field, e)),
+        None => Ok(None),
+    }
+}
+
+fn compile_rule(rule: Rule) -> Result<CompiledRule, String> {
+    let headline = compile_pattern(&rule.name, "match.headline", &rule.criteria.headline)?;
+    let backtrace = compile_pattern(&rule.name, "match.backtrace", &rule.criteria.backtrace)?;
+    let asan_body = compile_pattern(&rule.name, "match.asan_body", &rule.criteria.asan_body)?;
+    let ubsan_body = compile_pattern(&rule.name, "match.ubsan_body", &rule.criteria.ubsan_body)?;
+    let crashing_function =
+        compile_pattern(&rule.name, "match.crashing_function", &rule.criteria.crashing_function)?;
+
+    if headline.is_none()
+        && backtrace.is_none()
+        && asan_body.is_none()
+        && ubsan_body.is_none()
+        && crashing_function.is_none()
+    {
+        log::warn!("Rule \"{}\" has no match criteria and will never fire", rule.name);
+    }
+
+    Ok(CompiledRule {
+        name: rule.name,
+        headline,
+        backtrace,
+        asan_body,
+        ubsan_body,
+        crashing_function,
+        action: rule.action,
+    })
+}
+
+/// Load rules from a TOML or JSON file (selected by its extension, defaulting
+/// to TOML), compiling every `match` pattern into a `Regex` up front.
+pub fn load_rules(path: &Path) -> Result<Vec<CompiledRule>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+
+    let is_json = path.extension().and_then(|ext| ext.to_str()) == Some("json");
+
+    let rule_file: RuleFile = if is_json {
+        serde_json::from_str(&contents).map_err(|e| format!("invalid JSON rules file: {}", e))?
+    } else {
+        toml::from_str(&contents).map_err(|e| format!("invalid TOML rules file: {}", e))?
+    };
+
+    rule_file.rule.into_iter().map(compile_rule).collect()
+}
+
+/// Whether every populated criterion in `rule` matches `report`/
+/// `crashing_function`. A rule with no criteria never matches.
+fn rule_matches(rule: &CompiledRule, report: &TriageReport, crashing_function: &str) -> bool {
+    let checks: [(&Option<Regex>, &str); 5] = [
+        (&rule.headline, report.headline.as_str()),
+        (&rule.backtrace, report.backtrace.as_str()),
+        (&rule.asan_body, report.asan_body.as_str()),
+        (&rule.ubsan_body, report.ubsan_body.as_str()),
+        (&rule.crashing_function, crashing_function),
+    ];
+
+    let mut matched_any = false;
+
+    for (criterion, haystack) in checks {
+        if let Some(re) = criterion {
+            if !re.is_match(haystack) {
+                return false;
+            }
+
+            matched_any = true;
+        }
+    }
+
+    matched_any
+}
+
+/// Evaluate every rule against a crash's rendered report, in file order,
+/// returning the `(rule name, verdict)` of each rule that matched. A crash
+/// can match any number of rules; the caller decides how to combine them
+/// (e.g. accumulating every tag, but using the last routing/suppression
+/// decision).
+pub fn evaluate(
+    rules: &[CompiledRule],
+    report: &TriageReport,
+    crashing_function: &str,
+) -> Vec<(String, RuleVerdict)> {
+    rules
+        .iter()
+        .filter(|rule| rule_matches(rule, report, crashing_function))
+        .map(|rule| {
+            let verdict = match &rule.action {
+                RuleAction::Tag { label } => RuleVerdict::Tag(label.clone()),
+                RuleAction::OverrideSeverity { severity } => RuleVerdict::OverrideSeverity(*severity),
+                RuleAction::Route { subdir } => RuleVerdict::Route(subdir.clone()),
+                RuleAction::Suppress => RuleVerdict::Suppress,
+            };
+
+            (rule.name.clone(), verdict)
+        })
+        .collect()
+}
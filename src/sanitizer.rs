@@ -0,0 +1,123 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+//
+// UndefinedBehaviorSanitizer (UBSAN) diagnostic detection, modeled on
+// casr-ubsan: UBSAN doesn't raise a signal of its own, so its "runtime
+// error: ..." lines have to be scraped out of the child's captured output
+// and classified, the same way an ASAN report body is used elsewhere in
+// triage.
+use regex::Regex;
+
+arg_enum! {
+    #[derive(PartialEq, Debug, Clone, Copy)]
+
+    // user facing
+    #[allow(non_camel_case_types)]
+    pub enum SanitizerSelector {
+        asan,
+        ubsan,
+        both
+    }
+}
+
+impl SanitizerSelector {
+    pub fn wants_asan(&self) -> bool {
+        matches!(self, SanitizerSelector::asan | SanitizerSelector::both)
+    }
+
+    pub fn wants_ubsan(&self) -> bool {
+        matches!(self, SanitizerSelector::ubsan | SanitizerSelector::both)
+    }
+}
+
+/// A single UBSAN diagnostic parsed out of the child's captured output.
+#[derive(Debug, Clone)]
+pub struct UbsanDiagnostic {
+    /// e.g. "src/parse.c:42:9"
+    pub location: String,
+    /// A short, stable signature for dedup/severity, e.g. "signed-integer-overflow"
+    pub kind: String,
+    /// The full text after "runtime error: "
+    pub message: String,
+}
+
+lazy_static! {
+    /// UBSAN's default "-fsanitize=undefined" diagnostic format is
+    /// "<file>:<line>:<col>: runtime error: <message>".
+    static ref UBSAN_ERROR_RE: Regex =
+        Regex::new(r"(?m)^(?P<loc>[^\s:][^\n]*?:\d+:\d+): runtime error: (?P<msg>[^\n]+)$").unwrap();
+
+    /// AddressSanitizer doesn't raise a signal of its own either; its report
+    /// is a free-form block starting with "==<pid>==ERROR: AddressSanitizer:
+    /// <kind> ...", the only place a crash's real ASAN bug class (e.g.
+    /// use-after-free, heap-buffer-overflow) and READ/WRITE access are
+    /// recorded.
+    static ref ASAN_ERROR_RE: Regex = Regex::new(r"(?m)^==\d+==ERROR: AddressSanitizer:").unwrap();
+}
+
+/// Classify the free-form UBSAN message into a short, stable kind used for
+/// dedup and severity, mirroring the categories UBSAN itself can be
+/// configured to halt on (signed-integer-overflow, null, bounds, ...).
+fn classify_kind(message: &str) -> String {
+    let lowered = message.to_lowercase();
+
+    // "unsigned integer overflow" contains "signed integer overflow", so it
+    // has to be checked first.
+    if lowered.contains("unsigned integer overflow") {
+        "unsigned-integer-overflow".to_string()
+    } else if lowered.contains("signed integer overflow") {
+        "signed-integer-overflow".to_string()
+    } else if lowered.contains("null pointer") {
+        "null-pointer-use".to_string()
+    } else if lowered.contains("misaligned address") {
+        "misaligned-pointer-use".to_string()
+    } else if lowered.contains("index") && lowered.contains("out of bounds") {
+        "out-of-bounds-index".to_string()
+    } else if lowered.contains("division") && lowered.contains("zero") {
+        "divide-by-zero".to_string()
+    } else if lowered.contains("load of value") {
+        "invalid-bool-or-enum-load".to_string()
+    } else if lowered.contains("member call") || lowered.contains("vtable") {
+        "invalid-object-use".to_string()
+    } else {
+        "undefined-behavior".to_string()
+    }
+}
+
+/// Scrape every UBSAN diagnostic line out of `output` (either the child's
+/// captured stdout or stderr; UBSAN writes to stderr by default).
+pub fn extract_ubsan_diagnostics(output: &str) -> Vec<UbsanDiagnostic> {
+    UBSAN_ERROR_RE
+        .captures_iter(output)
+        .map(|caps| {
+            let message = caps["msg"].to_string();
+            UbsanDiagnostic {
+                location: caps["loc"].to_string(),
+                kind: classify_kind(&message),
+                message,
+            }
+        })
+        .collect()
+}
+
+/// Scrape the ASAN report body (if any) out of `output`, from its
+/// "==<pid>==ERROR: AddressSanitizer: ..." line to the end. Unlike UBSAN,
+/// ASAN emits one report per crash rather than several discrete
+/// diagnostics, so callers get the raw block back for both display and
+/// `severity::classify` to pattern-match against, instead of a parsed list.
+pub fn extract_asan_body(output: &str) -> Option<String> {
+    let start = ASAN_ERROR_RE.find(output)?.start();
+
+    Some(output[start..].trim_end().to_string())
+}
+
+/// Render the diagnostics the way the rest of a triage report's text/markdown
+/// sections are rendered: one line per diagnostic.
+pub fn format_report_section(diagnostics: &[UbsanDiagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| format!("{}: {} [{}]", d.location, d.message, d.kind))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
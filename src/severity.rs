@@ -0,0 +1,305 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+//
+// Crash severity classification modeled on the jfoote/exploitable and apport
+// heuristics (and the similar classifier in CASR): given the signal, faulting
+// instruction pointer/address, and backtrace that GDB extracted, guess how
+// dangerous a crash is likely to be.
+use serde::{Deserialize, Serialize};
+
+use crate::gdb_triage::{GdbContextInfo, GdbFrameInfo};
+use crate::sanitizer::UbsanDiagnostic;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum Severity {
+    EXPLOITABLE,
+    PROBABLY_EXPLOITABLE,
+    PROBABLY_NOT_EXPLOITABLE,
+    UNKNOWN,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::EXPLOITABLE => "EXPLOITABLE",
+            Severity::PROBABLY_EXPLOITABLE => "PROBABLY_EXPLOITABLE",
+            Severity::PROBABLY_NOT_EXPLOITABLE => "PROBABLY_NOT_EXPLOITABLE",
+            Severity::UNKNOWN => "UNKNOWN",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeverityResult {
+    pub severity: Severity,
+    /// A short human-readable explanation of why this verdict was reached
+    pub rationale: String,
+}
+
+// How many repeated occurrences of the same frame in a row before we call it
+// stack exhaustion rather than a "normal" crash
+const RECURSION_THRESHOLD: usize = 15;
+
+// Heuristic distance (in bytes) from the stack pointer that a faulting
+// address is considered "near the stack" for PROBABLY_EXPLOITABLE purposes
+const NEAR_STACK_PTR_WINDOW: u64 = 65536;
+
+fn is_write_access_violation(signal_code: i32) -> bool {
+    // On Linux, si_code 2 (SEGV_ACCERR) and the BUS_ADRERR family generally
+    // indicate a protection fault rather than a simple unmapped read
+    signal_code == 2
+}
+
+/// Whether the backtrace shows glibc's stack-protector catching a smashed
+/// stack canary (`__stack_chk_fail`), which indicates the target itself
+/// detected that an adjacent stack buffer had been overflowed.
+fn has_stack_smash(backtrace: &[GdbFrameInfo]) -> bool {
+    backtrace.iter().any(|frame| {
+        frame
+            .symbol
+            .as_ref()
+            .and_then(|sym| sym.function_name.as_ref())
+            .map(|name| name.contains("__stack_chk_fail"))
+            .unwrap_or(false)
+    })
+}
+
+fn has_deeply_recursive_backtrace(backtrace: &[GdbFrameInfo]) -> bool {
+    let mut run = 1;
+
+    for window in backtrace.windows(2) {
+        let same_frame = match (&window[0].symbol, &window[1].symbol) {
+            (Some(a), Some(b)) => a.function_name == b.function_name,
+            _ => window[0].relative_address == window[1].relative_address,
+        };
+
+        if same_frame {
+            run += 1;
+
+            if run >= RECURSION_THRESHOLD {
+                return true;
+            }
+        } else {
+            run = 1;
+        }
+    }
+
+    false
+}
+
+fn classify_asan_body(asan_body: &str) -> Option<SeverityResult> {
+    if asan_body.is_empty() {
+        return None;
+    }
+
+    let lowered = asan_body.to_lowercase();
+
+    if lowered.contains("use-after-free") {
+        return Some(SeverityResult {
+            severity: Severity::EXPLOITABLE,
+            rationale: "ASAN reported a use-after-free".to_string(),
+        });
+    }
+
+    if lowered.contains("double-free") {
+        return Some(SeverityResult {
+            severity: Severity::EXPLOITABLE,
+            rationale: "ASAN reported a double-free".to_string(),
+        });
+    }
+
+    if lowered.contains("heap-buffer-overflow") || lowered.contains("heap corruption") {
+        return Some(if lowered.contains("write") {
+            SeverityResult {
+                severity: Severity::EXPLOITABLE,
+                rationale: "ASAN reported a heap buffer overflow WRITE".to_string(),
+            }
+        } else {
+            SeverityResult {
+                severity: Severity::PROBABLY_EXPLOITABLE,
+                rationale: "ASAN reported heap corruption (overflow/READ)".to_string(),
+            }
+        });
+    }
+
+    if lowered.contains("stack-buffer-overflow") {
+        return Some(if lowered.contains("write") {
+            SeverityResult {
+                severity: Severity::EXPLOITABLE,
+                rationale: "ASAN reported a stack buffer overflow WRITE".to_string(),
+            }
+        } else {
+            SeverityResult {
+                severity: Severity::PROBABLY_EXPLOITABLE,
+                rationale: "ASAN reported a stack buffer overflow".to_string(),
+            }
+        });
+    }
+
+    if lowered.contains("global-buffer-overflow") {
+        return Some(if lowered.contains("write") {
+            SeverityResult {
+                severity: Severity::EXPLOITABLE,
+                rationale: "ASAN reported a global buffer overflow WRITE".to_string(),
+            }
+        } else {
+            SeverityResult {
+                severity: Severity::PROBABLY_EXPLOITABLE,
+                rationale: "ASAN reported a global buffer overflow".to_string(),
+            }
+        });
+    }
+
+    None
+}
+
+/// UBSAN kinds that indicate memory safety rather than just "undefined but
+/// probably harmless" behavior (e.g. an unsigned overflow) are worth raising
+/// above UNKNOWN, mirroring how casr-ubsan weights its own diagnostics.
+fn classify_ubsan_diagnostics(ubsan_diagnostics: &[UbsanDiagnostic]) -> Option<SeverityResult> {
+    for diag in ubsan_diagnostics {
+        match diag.kind.as_str() {
+            "null-pointer-use" | "misaligned-pointer-use" | "invalid-object-use" => {
+                return Some(SeverityResult {
+                    severity: Severity::PROBABLY_EXPLOITABLE,
+                    rationale: format!("UBSAN reported {} ({})", diag.kind, diag.location),
+                });
+            }
+            "out-of-bounds-index" => {
+                return Some(SeverityResult {
+                    severity: Severity::PROBABLY_EXPLOITABLE,
+                    rationale: format!("UBSAN reported an out-of-bounds index ({})", diag.location),
+                });
+            }
+            _ => (),
+        }
+    }
+
+    ubsan_diagnostics.first().map(|diag| SeverityResult {
+        severity: Severity::UNKNOWN,
+        rationale: format!("UBSAN reported {} ({}), not directly indicative of exploitability", diag.kind, diag.location),
+    })
+}
+
+/// Classify the severity of a crash using the GDB-extracted stop info,
+/// backtrace, and (if present) the ASAN report body and UBSAN diagnostics.
+/// Mirrors the !exploitable / apport heuristics that CASR's severity classes
+/// are built on.
+pub fn classify(ctx: &GdbContextInfo, asan_body: &str, ubsan_diagnostics: &[UbsanDiagnostic]) -> SeverityResult {
+    let backtrace = &ctx.primary_thread.backtrace;
+
+    if has_stack_smash(backtrace) {
+        return SeverityResult {
+            severity: Severity::PROBABLY_EXPLOITABLE,
+            rationale: "target's stack protector caught a smashed stack canary (__stack_chk_fail)"
+                .to_string(),
+        };
+    }
+
+    if has_deeply_recursive_backtrace(backtrace) {
+        return SeverityResult {
+            severity: Severity::PROBABLY_NOT_EXPLOITABLE,
+            rationale: "backtrace shows a deeply recursive call chain (likely stack exhaustion)"
+                .to_string(),
+        };
+    }
+
+    if let Some(asan_verdict) = classify_asan_body(asan_body) {
+        return asan_verdict;
+    }
+
+    if let Some(ubsan_verdict) = classify_ubsan_diagnostics(ubsan_diagnostics) {
+        return ubsan_verdict;
+    }
+
+    let lowered_asan = asan_body.to_lowercase();
+    if lowered_asan.contains("segv on unknown address") && lowered_asan.contains("read memory access") {
+        return SeverityResult {
+            severity: Severity::PROBABLY_NOT_EXPLOITABLE,
+            rationale: "ASAN reported a SEGV on read of an unmapped address".to_string(),
+        };
+    }
+
+    match ctx.stop_info.signal.as_str() {
+        "SIGABRT" => SeverityResult {
+            severity: Severity::PROBABLY_EXPLOITABLE,
+            rationale: "target aborted (SIGABRT), often from a corruption detector firing"
+                .to_string(),
+        },
+        "SIGSEGV" | "SIGBUS" => {
+            let pc = ctx
+                .primary_thread
+                .backtrace
+                .get(0)
+                .map(|f| f.address)
+                .unwrap_or(0);
+            let fault_addr = ctx.stop_info.faulting_address;
+
+            if let Some(addr) = fault_addr {
+                if addr == pc {
+                    return SeverityResult {
+                        severity: Severity::EXPLOITABLE,
+                        rationale: "faulting address equals the instruction pointer (likely a call/jmp to a tainted address)"
+                            .to_string(),
+                    };
+                }
+
+                if is_write_access_violation(ctx.stop_info.signal_code) {
+                    return SeverityResult {
+                        severity: Severity::EXPLOITABLE,
+                        rationale: "write access violation at the faulting address".to_string(),
+                    };
+                }
+
+                let sp = ctx
+                    .primary_thread
+                    .registers
+                    .as_ref()
+                    .and_then(|regs| regs.iter().find(|r| r.name == "rsp" || r.name == "sp"))
+                    .and_then(|r| r.as_u64());
+
+                if let Some(sp) = sp {
+                    let distance = addr.max(sp) - addr.min(sp);
+                    if distance < NEAR_STACK_PTR_WINDOW {
+                        return SeverityResult {
+                            severity: Severity::PROBABLY_EXPLOITABLE,
+                            rationale: "faulting address is near the stack pointer".to_string(),
+                        };
+                    }
+                }
+
+                let pc_distance = addr.max(pc) - addr.min(pc);
+                if pc_distance > (1 << 20) {
+                    return SeverityResult {
+                        severity: Severity::PROBABLY_NOT_EXPLOITABLE,
+                        rationale:
+                            "read access violation far from the instruction pointer with a large offset"
+                                .to_string(),
+                    };
+                }
+            }
+
+            SeverityResult {
+                severity: Severity::UNKNOWN,
+                rationale: "segmentation fault without enough context to classify further"
+                    .to_string(),
+            }
+        }
+        "SIGILL" | "SIGFPE" => SeverityResult {
+            severity: Severity::UNKNOWN,
+            rationale: format!("{} is not directly indicative of exploitability", ctx.stop_info.signal),
+        },
+        _ => SeverityResult {
+            severity: Severity::UNKNOWN,
+            rationale: "no heuristic matched the available crash context".to_string(),
+        },
+    }
+}
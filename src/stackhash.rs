@@ -0,0 +1,99 @@
+// Copyright (c) 2021, Qualcomm Innovation Center, Inc. All rights reserved.
+//
+// SPDX-License-Identifier: BSD-3-Clause
+//
+// Two-level crash bucketing, modeled on CASR's cluster hashing: a *minor*
+// hash over the whole normalized backtrace (over-splits less than a raw
+// stackhash, since addresses and anonymous offsets are stripped) and a
+// *major* hash over only the top N "meaningful" frames, skipping libc/ASAN/
+// sanitizer runtime frames. Reports are deduplicated primarily by major
+// hash, with minor hash used to report how many variants exist within a
+// bucket.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::gdb_triage::GdbFrameInfo;
+
+lazy_static! {
+    /// Frames that should not count towards the major hash because they are
+    /// runtime/libc/sanitizer noise rather than application logic.
+    pub static ref DEFAULT_FRAME_SKIP_REGEX: Regex = Regex::new(
+        r"^(__asan_|__ubsan_|__sanitizer_|asan\.module|std::panicking|core::panicking|_IO_|__libc_|__GI_)"
+    ).unwrap();
+}
+
+pub const DEFAULT_MAJOR_HASH_FRAMES: usize = 5;
+
+/// A single normalized frame identifier: function name when known (as
+/// `function@file`, with the source file's basename, when that is known
+/// too), otherwise the module and the relative (load-bias-independent)
+/// offset.
+fn normalize_frame(frame: &GdbFrameInfo) -> String {
+    match &frame.symbol {
+        Some(sym) => match &sym.file {
+            Some(file) => {
+                let basename = Path::new(file)
+                    .file_name()
+                    .map(|f| f.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| file.clone());
+                format!("{}@{}", sym.format_short(), basename)
+            }
+            None => sym.format_short(),
+        },
+        None => format!("{}+{:#x}", frame.module, frame.relative_address),
+    }
+}
+
+fn hash_of(parts: &[String]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for part in parts {
+        part.hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// The full backtrace as a sequence of normalized frame identifiers, for
+/// callers (e.g. fuzzy clustering) that need the sequence itself rather than
+/// a hash of it.
+pub fn normalized_trace(backtrace: &[GdbFrameInfo]) -> Vec<String> {
+    backtrace.iter().map(normalize_frame).collect()
+}
+
+/// Compute the minor hash: over the full normalized backtrace.
+pub fn minor_hash(backtrace: &[GdbFrameInfo]) -> String {
+    hash_of(&normalized_trace(backtrace))
+}
+
+/// Compute the major hash: over the first `major_hash_frames` normalized
+/// frames that don't match `frame_skip_regex`, plus an optional extra
+/// signature component (e.g. a UBSAN diagnostic kind) so that two crashes
+/// with the same backtrace but different undefined-behavior kinds don't get
+/// folded into a single bucket.
+pub fn major_hash(
+    backtrace: &[GdbFrameInfo],
+    major_hash_frames: usize,
+    frame_skip_regex: &Regex,
+    extra_signature: Option<&str>,
+) -> String {
+    let mut meaningful: Vec<String> = backtrace
+        .iter()
+        .map(normalize_frame)
+        .filter(|name| !frame_skip_regex.is_match(name))
+        .take(major_hash_frames)
+        .collect();
+
+    // Fall back to the full (unfiltered) set of frames if every frame in the
+    // backtrace looked like runtime noise, so we never produce an empty hash.
+    if meaningful.is_empty() {
+        meaningful = vec![minor_hash(backtrace)];
+    }
+
+    if let Some(extra) = extra_signature {
+        meaningful.push(extra.to_string());
+    }
+
+    hash_of(&meaningful)
+}